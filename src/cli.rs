@@ -1,7 +1,11 @@
 // src/cli.rs
 
+use std::path::PathBuf;
+
 use clap::Parser;
 
+use crate::output::OutputFormat;
+
 #[derive(Parser, Debug)]
 #[command(
     name = "subrapid",
@@ -10,11 +14,19 @@ use clap::Parser;
     about = "A tool to gather subdomains from a given URL"
 )]
 pub struct Cli {
-    /// The starting URL (e.g. "https://example.com")
-    pub url: String,
+    /// The starting URL (e.g. "https://example.com") or bare domain.
+    /// Use "-" to read one target per line from stdin.
+    /// Optional when --root-domain or --targets-file is given.
+    #[arg(required_unless_present_any = ["list_sources", "root_domain", "targets_file"])]
+    pub url: Option<String>,
+
+    /// File with one target (URL or bare domain) per line
+    #[arg(long)]
+    pub targets_file: Option<PathBuf>,
 
     /// Root domain to scope to (e.g. stackexchange.com).
-    /// If omitted, you can derive it from the URL's host in main().
+    /// If omitted, it is derived from each target's host.
+    /// Only allowed with a single target.
     #[arg(long)]
     pub root_domain: Option<String>,
 
@@ -25,4 +37,136 @@ pub struct Cli {
     /// Maximum pages to crawl per host (to avoid explosion)
     #[arg(long, default_value_t = 5)]
     pub max_pages_per_host: usize,
+
+    /// Maximum JavaScript files to fetch and scan for hostnames per host (0 disables)
+    #[arg(long, default_value_t = 10)]
+    pub max_scripts_per_host: usize,
+
+    /// Don't fetch robots.txt and sitemaps of crawled hosts
+    #[arg(long)]
+    pub no_sitemaps: bool,
+
+    /// Cap on crawler requests per second across all hosts (unlimited if omitted)
+    #[arg(long, value_name = "RPS")]
+    pub rate_limit: Option<f64>,
+
+    /// Minimum delay between two crawler requests to the same host, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    pub host_delay: u64,
+
+    /// Only run these sources (comma-separated ids, see --list-sources)
+    #[arg(long, value_delimiter = ',', value_name = "IDS")]
+    pub sources: Vec<String>,
+
+    /// Skip these sources (comma-separated ids, see --list-sources)
+    #[arg(long, value_delimiter = ',', value_name = "IDS")]
+    pub exclude_sources: Vec<String>,
+
+    /// Only run third-party (passive) sources; never send traffic to the target
+    #[arg(long)]
+    pub passive: bool,
+
+    /// List available sources and exit
+    #[arg(long)]
+    pub list_sources: bool,
+
+    /// HTTP request timeout in seconds
+    #[arg(long, default_value_t = crate::http::DEFAULT_TIMEOUT.as_secs())]
+    pub timeout: u64,
+
+    /// User-Agent header for all HTTP requests
+    #[arg(long, default_value = crate::http::DEFAULT_USER_AGENT)]
+    pub user_agent: String,
+
+    /// Extra header ("Name: value") sent only to hosts inside the root domain. Repeatable.
+    #[arg(long = "header", short = 'H', value_name = "NAME: VALUE")]
+    pub headers: Vec<String>,
+
+    /// Cookie ("name=value") sent only to hosts inside the root domain. Repeatable.
+    #[arg(long = "cookie", value_name = "NAME=VALUE")]
+    pub cookies: Vec<String>,
+
+    /// How many times to retry an HTTP request after a transient failure
+    /// (timeouts, connection errors, truncated bodies, 408/425/500/502/504;
+    /// 429/503 too for third-party sources)
+    #[arg(long, value_name = "NUM", default_value_t = 2)]
+    pub retries: u32,
+
+    /// Upstream proxy for every connection: "http://127.0.0.1:8080",
+    /// "socks5h://127.0.0.1:1080", or "direct" to ignore HTTP(S)_PROXY
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// Proxy for a single source, overriding --proxy (repeatable)
+    #[arg(long = "source-proxy", value_name = "ID=URL")]
+    pub source_proxies: Vec<String>,
+
+    /// Hosts, domains, IPs or CIDRs that skip the proxy (comma-separated).
+    /// Defaults to the NO_PROXY environment variable
+    #[arg(long = "no-proxy", value_name = "LIST")]
+    pub no_proxy: Option<String>,
+
+    /// Wordlist for DNS bruteforcing (one label per line).
+    /// DNS bruteforce runs by default only when this is given.
+    #[arg(long)]
+    pub wordlist: Option<PathBuf>,
+
+    /// Comma-separated DNS resolvers (e.g. "1.1.1.1,8.8.8.8:53")
+    #[arg(long, value_delimiter = ',', default_value = "1.1.1.1,8.8.8.8")]
+    pub resolvers: Vec<String>,
+
+    /// Comma-separated ports whose TLS certificates the tls-san source reads
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "PORTS",
+        default_value = "443"
+    )]
+    pub tls_ports: Vec<u16>,
+
+    /// RFC 6962 Certificate Transparency log to scan (base URL, repeatable or
+    /// comma-separated). The ct-logs source runs by default only when this is given.
+    #[arg(long = "ct-log", value_delimiter = ',', value_name = "URL")]
+    pub ct_logs: Vec<String>,
+
+    /// File recording how far each CT log has been scanned, so the next run resumes there
+    #[arg(long, value_name = "FILE")]
+    pub ct_checkpoint: Option<PathBuf>,
+
+    /// CT log entries scanned per log and run (default 10000)
+    #[arg(long, value_name = "NUM")]
+    pub ct_max_entries: Option<u64>,
+
+    /// Keep every archived URL the Wayback Machine has, with capture time and status
+    #[arg(long)]
+    pub wayback_all_paths: bool,
+
+    /// Number of Common Crawl indexes to query, newest first (default 3)
+    #[arg(long, value_name = "NUM")]
+    pub commoncrawl_indexes: Option<usize>,
+
+    /// Base URL for an API source, e.g. "urlscan=https://urlscan.internal/" (repeatable)
+    #[arg(long = "source-base-url", value_name = "ID=URL")]
+    pub source_base_urls: Vec<String>,
+
+    /// API key for a source, e.g. "certspotter=KEY" (repeatable)
+    #[arg(long = "api-key", value_name = "ID=KEY")]
+    pub api_keys: Vec<String>,
+
+    /// Output format for the results
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plain)]
+    pub output_format: OutputFormat,
+
+    /// Write results to this file instead of stdout
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// Wall-clock timeout per source in seconds (default 600).
+    /// Use NAME=SECS to override a single source, e.g. "crtsh=60". Repeatable.
+    #[arg(long, value_name = "[NAME=]SECS")]
+    pub source_timeout: Vec<String>,
+
+    /// Exit with a non-zero status if any source failed outright
+    #[arg(long)]
+    pub fail_on_source_error: bool,
 }
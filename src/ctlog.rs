@@ -0,0 +1,280 @@
+// src/ctlog.rs
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail, ensure};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use openssl::x509::X509;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::http::{HttpClient, HttpStatusError};
+
+/// `MerkleTreeLeaf.leaf_type` of a `TimestampedEntry` (the only one defined).
+const TIMESTAMPED_ENTRY: u8 = 0;
+
+/// `LogEntryType` values.
+const X509_ENTRY: u16 = 0;
+const PRECERT_ENTRY: u16 = 1;
+
+/// A Certificate Transparency log speaking the RFC 6962 API.
+#[derive(Clone, Debug)]
+pub struct CtLog {
+    /// Base URL, e.g. "https://ct.googleapis.com/logs/us1/argon2025h2/"
+    pub url: Url,
+}
+
+/// The part of `get-sth` we need.
+#[derive(Deserialize, Debug)]
+struct SignedTreeHead {
+    tree_size: u64,
+}
+
+#[derive(Deserialize, Debug)]
+struct GetEntries {
+    entries: Vec<RawEntry>,
+}
+
+/// One item of `get-entries`, both fields base64.
+#[derive(Deserialize, Debug)]
+struct RawEntry {
+    leaf_input: String,
+    extra_data: String,
+}
+
+impl CtLog {
+    /// Parse a log URL; the "/ct/v1/" API path is appended to it.
+    pub fn parse(url: &str) -> Result<Self> {
+        let mut url =
+            Url::parse(url.trim()).with_context(|| format!("invalid CT log URL: {}", url))?;
+        ensure!(
+            matches!(url.scheme(), "http" | "https"),
+            "CT log URL must be http(s): {}",
+            url
+        );
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        Ok(Self { url })
+    }
+
+    /// Number of entries in the log (`get-sth`).
+    pub fn tree_size(&self, http: &HttpClient) -> Result<u64> {
+        let sth: SignedTreeHead = self.get_json(http, "ct/v1/get-sth")?;
+        Ok(sth.tree_size)
+    }
+
+    /// Certificates at indexes `start..=end` (`get-entries`).
+    ///
+    /// Logs may return fewer entries than asked for; the caller continues
+    /// from `start + returned`. Entries that cannot be parsed come back as errors
+    /// so one odd certificate does not lose the whole batch.
+    pub fn entries(&self, http: &HttpClient, start: u64, end: u64) -> Result<Vec<Result<X509>>> {
+        let path = format!("ct/v1/get-entries?start={}&end={}", start, end);
+        let batch: GetEntries = self.get_json(http, &path)?;
+        Ok(batch
+            .entries
+            .iter()
+            .map(|e| parse_entry(&e.leaf_input, &e.extra_data))
+            .collect())
+    }
+
+    /// Where a single entry can be looked up again, for evidence.
+    pub fn entry_url(&self, index: u64) -> String {
+        format!(
+            "{}ct/v1/get-entries?start={}&end={}",
+            self.url, index, index
+        )
+    }
+
+    fn get_json<T: for<'de> Deserialize<'de>>(&self, http: &HttpClient, path: &str) -> Result<T> {
+        let url = self.url.join(path)?;
+        let resp = http
+            .get(url.as_str())
+            .send()
+            .with_context(|| format!("failed to GET {}", url))?;
+
+        if !resp.status().is_success() {
+            return Err(
+                anyhow::Error::from(HttpStatusError::from_response(&resp)).context(format!(
+                    "CT log {} returned non-success status code",
+                    self.url
+                )),
+            );
+        }
+
+        resp.json()
+            .with_context(|| format!("invalid response from {}", url))
+    }
+}
+
+/// Decode a log entry into the certificate it logs.
+///
+/// X.509 entries carry the certificate in the `MerkleTreeLeaf`; for
+/// precertificates the leaf only has the TBSCertificate, so the full
+/// precertificate is taken from `extra_data` (`PrecertChainEntry`).
+pub fn parse_entry(leaf_input: &str, extra_data: &str) -> Result<X509> {
+    let leaf = STANDARD
+        .decode(leaf_input)
+        .context("leaf_input is not base64")?;
+
+    // version (1) | leaf_type (1) | timestamp (8) | entry_type (2) | entry
+    ensure!(leaf.len() >= 12, "truncated Merkle tree leaf");
+    ensure!(leaf[0] == 0, "unsupported leaf version {}", leaf[0]);
+    ensure!(
+        leaf[1] == TIMESTAMPED_ENTRY,
+        "unsupported leaf type {}",
+        leaf[1]
+    );
+
+    let der = match u16::from_be_bytes([leaf[10], leaf[11]]) {
+        X509_ENTRY => read_u24_prefixed(&leaf[12..])?.to_vec(),
+        PRECERT_ENTRY => {
+            let extra = STANDARD
+                .decode(extra_data)
+                .context("extra_data is not base64")?;
+            read_u24_prefixed(&extra)?.to_vec()
+        }
+        other => bail!("unknown log entry type {}", other),
+    };
+
+    X509::from_der(&der).context("invalid certificate in log entry")
+}
+
+/// An `opaque<1..2^24-1>`: 3-byte big-endian length, then the data.
+fn read_u24_prefixed(data: &[u8]) -> Result<&[u8]> {
+    ensure!(data.len() >= 3, "truncated length prefix");
+    let len = u32::from_be_bytes([0, data[0], data[1], data[2]]) as usize;
+    data.get(3..3 + len).context("truncated log entry")
+}
+
+/// How far each log has been scanned for each root domain, so a later run
+/// continues where the previous one stopped.
+///
+/// Stored as JSON: `{"example.com": {"https://log.example/": 1234}}`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Checkpoints {
+    #[serde(flatten)]
+    next_index: BTreeMap<String, BTreeMap<String, u64>>,
+
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Checkpoints {
+    /// Load checkpoints from `path`; a missing file means no checkpoints yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut checkpoints: Self = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("invalid CT checkpoint file {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("failed to read CT checkpoint file {}", path.display())
+                });
+            }
+        };
+        checkpoints.path = Some(path.to_path_buf());
+        Ok(checkpoints)
+    }
+
+    /// First index not yet scanned in `log` for `root_domain`.
+    pub fn get(&self, root_domain: &str, log: &CtLog) -> Option<u64> {
+        self.next_index
+            .get(root_domain)
+            .and_then(|logs| logs.get(log.url.as_str()))
+            .copied()
+    }
+
+    pub fn set(&mut self, root_domain: &str, log: &CtLog, next_index: u64) {
+        self.next_index
+            .entry(root_domain.to_string())
+            .or_default()
+            .insert(log.url.to_string(), next_index);
+    }
+
+    /// Write the checkpoints back to the file they were loaded from, if any.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Write to a sibling file first so an interrupted run cannot truncate it
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("failed to write CT checkpoint file {}", path.display()))
+    }
+}
+
+/// Build `leaf_input` / `extra_data` for a certificate, the way a log would.
+#[cfg(test)]
+pub(crate) fn encode_entry(cert: &X509, precert: bool) -> (String, String) {
+    let der = cert.to_der().unwrap();
+    let u24 = |len: usize| (len as u32).to_be_bytes()[1..].to_vec();
+
+    let mut leaf = vec![0, TIMESTAMPED_ENTRY];
+    leaf.extend_from_slice(&1_700_000_000_000u64.to_be_bytes());
+    let mut extra = Vec::new();
+
+    if precert {
+        leaf.extend_from_slice(&PRECERT_ENTRY.to_be_bytes());
+        leaf.extend_from_slice(&[0; 32]); // issuer_key_hash
+        // The real leaf holds the TBSCertificate; parse_entry never reads it
+        leaf.extend(u24(3));
+        leaf.extend_from_slice(&[1, 2, 3]);
+        extra.extend(u24(der.len()));
+        extra.extend_from_slice(&der);
+        extra.extend(u24(0)); // empty chain
+    } else {
+        leaf.extend_from_slice(&X509_ENTRY.to_be_bytes());
+        leaf.extend(u24(der.len()));
+        leaf.extend_from_slice(&der);
+        extra.extend(u24(0));
+    }
+    leaf.extend_from_slice(&[0, 0]); // no extensions
+
+    (STANDARD.encode(leaf), STANDARD.encode(extra))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls::{certificate_names, test_server};
+
+    #[test]
+    fn test_parse_x509_and_precert_entries() {
+        let (_, cert) = test_server::self_signed("www.example.com", &["api.example.com"]);
+
+        for precert in [false, true] {
+            let (leaf, extra) = encode_entry(&cert, precert);
+            let parsed = parse_entry(&leaf, &extra).unwrap();
+            let names = certificate_names(&parsed);
+            assert_eq!(names.common_name.as_deref(), Some("www.example.com"));
+            assert_eq!(names.alt_names, vec!["api.example.com"]);
+        }
+
+        assert!(parse_entry("AAE=", "").is_err());
+    }
+
+    #[test]
+    fn test_checkpoints_round_trip() {
+        let path = std::env::temp_dir().join(format!("subrapid-ct-{}.json", std::process::id()));
+        let log = CtLog::parse("https://ct.example.net/logs/2025").unwrap();
+        assert_eq!(log.url.as_str(), "https://ct.example.net/logs/2025/");
+
+        let mut checkpoints = Checkpoints::load(&path).unwrap();
+        assert_eq!(checkpoints.get("example.com", &log), None);
+        checkpoints.set("example.com", &log, 4096);
+        checkpoints.save().unwrap();
+
+        let reloaded = Checkpoints::load(&path).unwrap();
+        assert_eq!(reloaded.get("example.com", &log), Some(4096));
+        assert_eq!(reloaded.get("example.org", &log), None);
+        fs::remove_file(&path).unwrap();
+    }
+}
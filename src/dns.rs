@@ -0,0 +1,366 @@
+// src/dns.rs
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::proxy::ProxyMode;
+
+/// How long to wait for a single resolver to answer before trying the next one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+const RCODE_NXDOMAIN: u8 = 3;
+
+/// Answer records returned for a name that resolved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lookup {
+    pub addrs: Vec<IpAddr>,
+    pub cnames: Vec<String>,
+}
+
+/// A tiny stub resolver speaking plain DNS over UDP to a list of upstream servers.
+///
+/// Queries are spread over the servers round-robin; when one server times out
+/// the next one in the list is tried.
+pub struct Resolver {
+    servers: Vec<SocketAddr>,
+    timeout: Duration,
+    next: AtomicUsize,
+}
+
+impl Resolver {
+    pub fn new(servers: Vec<SocketAddr>, timeout: Duration) -> Self {
+        Self {
+            servers,
+            timeout,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Resolve `name` to its A records (following whatever CNAMEs the server returns).
+    ///
+    /// Returns `Ok(None)` for NXDOMAIN or an empty answer section.
+    pub fn resolve(&self, name: &str) -> Result<Option<Lookup>> {
+        if self.servers.is_empty() {
+            anyhow::bail!("no DNS resolvers configured");
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut last_err = None;
+
+        for i in 0..self.servers.len() {
+            let server = self.servers[(start + i) % self.servers.len()];
+            match self.query(server, name) {
+                Ok(lookup) => return Ok(lookup),
+                Err(e) => last_err = Some(e),
+            }
+        }
+
+        Err(last_err.unwrap())
+    }
+
+    fn query(&self, server: SocketAddr, name: &str) -> Result<Option<Lookup>> {
+        let bind_addr: SocketAddr = if server.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+
+        let socket = UdpSocket::bind(bind_addr).context("failed to bind UDP socket")?;
+        socket.set_read_timeout(Some(self.timeout))?;
+        socket
+            .connect(server)
+            .with_context(|| format!("failed to connect to resolver {}", server))?;
+
+        let id = query_id(name);
+        let packet = build_query(id, name, TYPE_A)?;
+        socket
+            .send(&packet)
+            .with_context(|| format!("failed to send query to resolver {}", server))?;
+
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = socket
+                .recv(&mut buf)
+                .with_context(|| format!("no answer from resolver {} for {}", server, name))?;
+
+            // Ignore stray datagrams that don't belong to our query
+            if len < 2 || u16::from_be_bytes([buf[0], buf[1]]) != id {
+                continue;
+            }
+
+            return parse_response(&buf[..len]);
+        }
+    }
+}
+
+/// Parse resolver strings such as "1.1.1.1", "1.1.1.1:5353" or "[2606:4700::1111]:53".
+pub fn parse_resolver(s: &str) -> Result<SocketAddr> {
+    let s = s.trim();
+    if let Ok(addr) = s.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+
+    let ip: IpAddr = s
+        .parse()
+        .with_context(|| format!("invalid resolver address: {}", s))?;
+    Ok(SocketAddr::new(ip, 53))
+}
+
+/// Refuse to send queries that would bypass a configured upstream proxy.
+///
+/// DNS goes straight out over UDP, which neither HTTP proxies nor SSH SOCKS
+/// tunnels can carry, so a resolver is only usable if the bypass list covers it.
+/// `source` names the source asking, for the hint in the error.
+pub fn ensure_unproxied(servers: &[SocketAddr], proxy: &ProxyMode, source: &str) -> Result<()> {
+    if let Some(server) = servers
+        .iter()
+        .find(|s| proxy.upstream_for(&s.ip().to_string()).is_some())
+    {
+        anyhow::bail!(
+            "DNS queries to {} cannot go through the proxy; add the resolver to --no-proxy \
+             or use --source-proxy {}=direct",
+            server,
+            source
+        );
+    }
+    Ok(())
+}
+
+/// Derive a transaction id that differs between queries without pulling in an RNG.
+fn query_id(name: &str) -> u16 {
+    use std::hash::{BuildHasher, Hasher};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write(name.as_bytes());
+    hasher.write_usize(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.finish() as u16
+}
+
+fn build_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&0x0100u16.to_be_bytes()); // standard query, recursion desired
+    packet.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    packet.extend_from_slice(&[0, 0, 0, 0, 0, 0]); // ANCOUNT, NSCOUNT, ARCOUNT
+
+    encode_name(&mut packet, name)?;
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+
+    Ok(packet)
+}
+
+fn encode_name(out: &mut Vec<u8>, name: &str) -> Result<()> {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            anyhow::bail!("invalid DNS name: {}", name);
+        }
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+    Ok(())
+}
+
+/// Read a (possibly compressed) name starting at `pos`.
+/// Returns the decoded name and the offset right after it in the original stream.
+fn read_name(packet: &[u8], mut pos: usize) -> Result<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *packet.get(pos).context("truncated DNS name")? as usize;
+
+        if len & 0xC0 == 0xC0 {
+            // Compression pointer
+            let lo = *packet.get(pos + 1).context("truncated DNS pointer")? as usize;
+            end.get_or_insert(pos + 2);
+            pos = ((len & 0x3F) << 8) | lo;
+
+            jumps += 1;
+            if jumps > 32 {
+                anyhow::bail!("DNS name compression loop");
+            }
+            continue;
+        }
+
+        if len == 0 {
+            let end = end.unwrap_or(pos + 1);
+            return Ok((labels.join("."), end));
+        }
+
+        let label = packet
+            .get(pos + 1..pos + 1 + len)
+            .context("truncated DNS label")?;
+        labels.push(String::from_utf8_lossy(label).to_lowercase());
+        pos += 1 + len;
+    }
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Result<u16> {
+    let bytes = packet.get(pos..pos + 2).context("truncated DNS packet")?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn parse_response(packet: &[u8]) -> Result<Option<Lookup>> {
+    if packet.len() < 12 {
+        anyhow::bail!("DNS response too short");
+    }
+
+    let rcode = packet[3] & 0x0F;
+    if rcode == RCODE_NXDOMAIN {
+        return Ok(None);
+    }
+    if rcode != 0 {
+        anyhow::bail!("resolver returned error code {}", rcode);
+    }
+
+    let qdcount = read_u16(packet, 4)?;
+    let ancount = read_u16(packet, 6)?;
+
+    // Skip the question section
+    let mut pos = 12;
+    for _ in 0..qdcount {
+        let (_, next) = read_name(packet, pos)?;
+        pos = next + 4;
+    }
+
+    let mut lookup = Lookup::default();
+    for _ in 0..ancount {
+        let (_, next) = read_name(packet, pos)?;
+        let rtype = read_u16(packet, next)?;
+        let rdlen = read_u16(packet, next + 8)? as usize;
+        let rdata_start = next + 10;
+        let rdata = packet
+            .get(rdata_start..rdata_start + rdlen)
+            .context("truncated DNS record")?;
+
+        match rtype {
+            TYPE_A if rdlen == 4 => {
+                lookup
+                    .addrs
+                    .push(IpAddr::from([rdata[0], rdata[1], rdata[2], rdata[3]]));
+            }
+            TYPE_AAAA if rdlen == 16 => {
+                let octets: [u8; 16] = rdata.try_into().unwrap();
+                lookup.addrs.push(IpAddr::from(octets));
+            }
+            TYPE_CNAME => {
+                let (cname, _) = read_name(packet, rdata_start)?;
+                lookup.cnames.push(cname);
+            }
+            _ => {}
+        }
+
+        pos = rdata_start + rdlen;
+    }
+
+    if lookup.addrs.is_empty() && lookup.cnames.is_empty() {
+        return Ok(None);
+    }
+
+    lookup.addrs.sort();
+    lookup.addrs.dedup();
+    Ok(Some(lookup))
+}
+
+/// A minimal authoritative-ish DNS server for tests, bound to 127.0.0.1.
+#[cfg(test)]
+pub(crate) mod stub_server {
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+    use std::thread;
+
+    use super::{read_name, read_u16};
+
+    /// Spawn a UDP server answering A queries from `records`; unknown names get NXDOMAIN.
+    ///
+    /// A key of the form "*.example.com" acts as a wildcard for any name below it.
+    pub fn spawn(records: HashMap<String, Ipv4Addr>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf) {
+                let query = &buf[..len];
+                let Ok((name, end)) = read_name(query, 12) else {
+                    continue;
+                };
+                let qtype = read_u16(query, end).unwrap_or(0);
+
+                let answer = records.get(&name).copied().or_else(|| {
+                    name.split_once('.')
+                        .and_then(|(_, parent)| records.get(&format!("*.{}", parent)).copied())
+                });
+
+                let mut resp = Vec::new();
+                resp.extend_from_slice(&query[..2]);
+                let rcode = if answer.is_some() { 0 } else { 3 };
+                resp.extend_from_slice(&[0x81, 0x80 | rcode]);
+                resp.extend_from_slice(&1u16.to_be_bytes());
+                let ancount = u16::from(answer.is_some() && qtype == 1);
+                resp.extend_from_slice(&ancount.to_be_bytes());
+                resp.extend_from_slice(&[0, 0, 0, 0]);
+                resp.extend_from_slice(&query[12..end + 4]);
+
+                if let (Some(ip), 1) = (answer, ancount) {
+                    resp.extend_from_slice(&[0xC0, 12]); // pointer to question name
+                    resp.extend_from_slice(&1u16.to_be_bytes()); // A
+                    resp.extend_from_slice(&1u16.to_be_bytes()); // IN
+                    resp.extend_from_slice(&60u32.to_be_bytes()); // TTL
+                    resp.extend_from_slice(&4u16.to_be_bytes());
+                    resp.extend_from_slice(&ip.octets());
+                }
+
+                let _ = socket.send_to(&resp, peer);
+            }
+        });
+
+        addr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_against_stub_server() {
+        let server = stub_server::spawn(HashMap::from([(
+            "www.example.com".to_string(),
+            Ipv4Addr::new(10, 0, 0, 1),
+        )]));
+        let resolver = Resolver::new(vec![server], DEFAULT_TIMEOUT);
+
+        let lookup = resolver.resolve("www.example.com").unwrap().unwrap();
+        assert_eq!(lookup.addrs, vec![IpAddr::from([10, 0, 0, 1])]);
+        assert!(resolver.resolve("nope.example.com").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_resolver() {
+        assert_eq!(
+            parse_resolver("1.1.1.1").unwrap(),
+            "1.1.1.1:53".parse().unwrap()
+        );
+        assert_eq!(
+            parse_resolver("127.0.0.1:5353").unwrap(),
+            "127.0.0.1:5353".parse().unwrap()
+        );
+        assert!(parse_resolver("not-an-ip").is_err());
+    }
+}
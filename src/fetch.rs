@@ -1,39 +1,389 @@
 // src/fetch.rs
 
-use anyhow::{Context, Result};
-use std::time::Duration;
+use std::io::Read;
+
+use anyhow::{Context, Result, bail};
+use reqwest::StatusCode;
+use reqwest::blocking::Response;
+use reqwest::header::{HeaderMap, LOCATION};
+use url::Url;
+
+use crate::http::{HttpClient, HttpStatusError};
+use crate::ratelimit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::sources::SourceContext;
+
+/// Redirects followed at most for a single page.
+const MAX_REDIRECTS: usize = 10;
 
 pub struct FetchedPage {
+    /// Where the body came from, after following redirects
+    pub final_url: Url,
+    pub redirects: Vec<RedirectHop>, // Redirects taken, in order (empty if none)
     pub body: String,
-    pub csp: Option<String>, // Content-Security-Policy header if present
+    pub headers: HeaderMap, // All response headers of the final response
 }
 
-/// Fetches the body and Content-Security-Policy header of the given URL.
-pub fn fetch_page(url: &str) -> Result<FetchedPage> {
-    let client = reqwest::blocking::Client::builder()
-        .timeout(Duration::from_secs(20))
-        .user_agent("subrapid-knightchaser/0.1")
-        .build()
-        .context("failed to build HTTP client")?;
+/// An optional file fetched by `fetch_file`.
+pub struct FetchedFile {
+    /// Where the body came from, after following redirects
+    pub final_url: Url,
+    pub redirects: Vec<RedirectHop>, // Redirects taken, in order (empty if none)
+    pub body: Vec<u8>,               // At most the requested number of bytes
+}
 
-    let resp = client
-        .get(url)
-        .send()
-        .with_context(|| format!("failed to GET {}", url))?;
+/// One redirect response on the way to a page.
+#[derive(Debug, Clone)]
+pub struct RedirectHop {
+    pub from: Url,
+    pub to: Url,
+    pub status: StatusCode,
+}
 
-    if !resp.status().is_success() {
-        anyhow::bail!("request failed with status: {}", resp.status());
-    }
+/// Fetches the body and response headers of the given URL
+/// on the target (`root_domain` decides whether cookies are sent).
+///
+/// Redirects are followed one hop at a time (at most `MAX_REDIRECTS`) and
+/// reported in `redirects`; relative links in the body belong to `final_url`.
+///
+/// Transient failures are retried according to `retry`; non-success responses
+/// that are not retried fail with an `HttpStatusError`. Every request, retries
+/// and redirect hops included, waits for `limiter` first.
+pub fn fetch_page(
+    http: &HttpClient,
+    limiter: &RateLimiter,
+    url: &Url,
+    root_domain: &str,
+    retry: &RetryPolicy,
+    ctx: &SourceContext,
+) -> Result<FetchedPage> {
+    retry.run(ctx, &format!("GET {}", url), || {
+        fetch_once(http, limiter, url, root_domain, ctx)
+    })
+}
 
-    let csp = resp
-        .headers()
-        .get("Content-Security-Policy")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+fn fetch_once(
+    http: &HttpClient,
+    limiter: &RateLimiter,
+    url: &Url,
+    root_domain: &str,
+    ctx: &SourceContext,
+) -> Result<FetchedPage> {
+    let (final_url, redirects, resp) = follow(http, limiter, url, root_domain, ctx)?;
+    let headers = resp.headers().clone();
 
     let body = resp
         .text()
         .context("failed to read response body as text")?;
 
-    Ok(FetchedPage { body, csp })
+    Ok(FetchedPage {
+        final_url,
+        redirects,
+        body,
+        headers,
+    })
+}
+
+/// GET `url`, following redirects one hop at a time, each hop waiting for
+/// `limiter`. Returns the final URL, the hops taken and the (successful)
+/// final response.
+fn follow(
+    http: &HttpClient,
+    limiter: &RateLimiter,
+    url: &Url,
+    root_domain: &str,
+    ctx: &SourceContext,
+) -> Result<(Url, Vec<RedirectHop>, Response)> {
+    let mut current = url.clone();
+    let mut redirects: Vec<RedirectHop> = Vec::new();
+
+    loop {
+        acquire(limiter, &current, ctx)?;
+        let resp = http
+            .get_hop(&current, root_domain)
+            .send()
+            .with_context(|| format!("failed to GET {}", current))?;
+        let status = resp.status();
+
+        if status.is_redirection()
+            && let Some(next) = redirect_target(&resp, &current)
+        {
+            if redirects.len() >= MAX_REDIRECTS {
+                bail!(
+                    "too many redirects (more than {}) from {}",
+                    MAX_REDIRECTS,
+                    url
+                );
+            }
+            if next == *url || redirects.iter().any(|hop| hop.to == next) {
+                bail!("redirect loop at {}", next);
+            }
+
+            redirects.push(RedirectHop {
+                from: current,
+                to: next.clone(),
+                status,
+            });
+            current = next;
+            continue;
+        }
+
+        if !status.is_success() {
+            return Err(HttpStatusError::from_response(&resp).into());
+        }
+
+        return Ok((current, redirects, resp));
+    }
+}
+
+/// The http(s) URL a redirect response points to, resolved against `current`.
+fn redirect_target(resp: &Response, current: &Url) -> Option<Url> {
+    let location = resp.headers().get(LOCATION)?.to_str().ok()?;
+    current
+        .join(location.trim())
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+/// Fetches an optional file on the target (robots.txt, a sitemap) as raw
+/// bytes, reading at most `max_bytes` of the body.
+///
+/// A 404 or 410 means the file does not exist and yields `None`; redirects,
+/// retries and other failures behave as in `fetch_page`.
+pub fn fetch_file(
+    http: &HttpClient,
+    limiter: &RateLimiter,
+    url: &Url,
+    root_domain: &str,
+    max_bytes: u64,
+    retry: &RetryPolicy,
+    ctx: &SourceContext,
+) -> Result<Option<FetchedFile>> {
+    let result = retry.run(ctx, &format!("GET {}", url), || {
+        let (final_url, redirects, resp) = follow(http, limiter, url, root_domain, ctx)?;
+        let mut body = Vec::new();
+        resp.take(max_bytes)
+            .read_to_end(&mut body)
+            .context("failed to read response body")?;
+        Ok(FetchedFile {
+            final_url,
+            redirects,
+            body,
+        })
+    });
+
+    match result {
+        Ok(file) => Ok(Some(file)),
+        Err(e)
+            if e.downcast_ref::<HttpStatusError>().is_some_and(|s| {
+                s.status == StatusCode::NOT_FOUND || s.status == StatusCode::GONE
+            }) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Wait until `limiter` lets a request to the host of `url` go out.
+fn acquire(limiter: &RateLimiter, url: &Url, ctx: &SourceContext) -> Result<()> {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    if !limiter.acquire(&host, ctx) {
+        bail!("cancelled while waiting to GET {}", url);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::http::HttpSettings;
+    use crate::http::stub_server::{self, response};
+    use crate::proxy::ProxyMode;
+    use crate::ratelimit::RateLimitSettings;
+
+    #[test]
+    fn test_fetch_page_follows_and_records_redirects() {
+        let port = stub_server::spawn(|req, port| match req.path.as_str() {
+            "/start" => {
+                let sso = format!("http://localhost:{port}/sso");
+                response("301 Moved Permanently", &[("Location", &sso)], "")
+            }
+            "/sso" => response("302 Found", &[("Location", "/app/")], ""),
+            _ => response("200 OK", &[], "hello"),
+        });
+
+        let http = HttpClient::new(&HttpSettings {
+            proxy: ProxyMode::Direct,
+            ..Default::default()
+        })
+        .unwrap();
+        let start = Url::parse(&format!("http://127.0.0.1:{port}/start")).unwrap();
+        let retry = RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        };
+
+        let limiter = RateLimiter::new(RateLimitSettings::default());
+        let page = fetch_page(
+            &http,
+            &limiter,
+            &start,
+            "127.0.0.1",
+            &retry,
+            &SourceContext::new(),
+        )
+        .unwrap();
+        assert_eq!(page.body, "hello");
+        assert_eq!(
+            page.final_url.as_str(),
+            format!("http://localhost:{port}/app/")
+        );
+
+        let hops: Vec<(String, u16)> = page
+            .redirects
+            .iter()
+            .map(|hop| (hop.to.to_string(), hop.status.as_u16()))
+            .collect();
+        assert_eq!(
+            hops,
+            vec![
+                (format!("http://localhost:{port}/sso"), 301),
+                (format!("http://localhost:{port}/app/"), 302),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retries_and_hops_wait_for_the_limiter() {
+        let hits = AtomicUsize::new(0);
+        let port = stub_server::spawn(move |req, _| match req.path.as_str() {
+            "/start" => response("302 Found", &[("Location", "/page")], ""),
+            _ if hits.fetch_add(1, Ordering::SeqCst) == 0 => response("502 Bad Gateway", &[], ""),
+            _ => response("200 OK", &[], "ok"),
+        });
+
+        let http = HttpClient::new(&HttpSettings::default()).unwrap();
+        let limiter = RateLimiter::new(RateLimitSettings {
+            requests_per_second: None,
+            per_host_delay: Duration::from_millis(150),
+        });
+        let retry = RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::ZERO,
+            ..Default::default()
+        };
+        let start = Url::parse(&format!("http://127.0.0.1:{port}/start")).unwrap();
+
+        // start -> 302 -> 502, then start -> 302 -> 200: four requests, three gaps
+        let began = Instant::now();
+        let page = fetch_page(
+            &http,
+            &limiter,
+            &start,
+            "127.0.0.1",
+            &retry,
+            &SourceContext::new(),
+        )
+        .unwrap();
+        assert_eq!(page.body, "ok");
+        assert!(began.elapsed() >= Duration::from_millis(450));
+    }
+
+    #[test]
+    fn test_fetch_file_follows_redirects_and_caps_the_body() {
+        let port = stub_server::spawn(|req, port| match req.path.as_str() {
+            "/robots.txt" => {
+                let moved = format!("http://localhost:{port}/static/robots.txt");
+                response("301 Moved Permanently", &[("Location", &moved)], "")
+            }
+            "/gone.xml" => response("410 Gone", &[], ""),
+            _ => response("200 OK", &[], "Sitemap: /sitemap.xml\n"),
+        });
+
+        let http = HttpClient::new(&HttpSettings::default()).unwrap();
+        let limiter = RateLimiter::new(RateLimitSettings::default());
+        let retry = RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        };
+        let ctx = SourceContext::new();
+
+        let robots = Url::parse(&format!("http://127.0.0.1:{port}/robots.txt")).unwrap();
+        let file = fetch_file(&http, &limiter, &robots, "127.0.0.1", 8, &retry, &ctx)
+            .unwrap()
+            .unwrap();
+        assert_eq!(file.body, b"Sitemap:");
+        assert_eq!(
+            file.final_url.as_str(),
+            format!("http://localhost:{port}/static/robots.txt")
+        );
+        assert_eq!(file.redirects.len(), 1);
+        assert_eq!(file.redirects[0].status.as_u16(), 301);
+
+        let gone = Url::parse(&format!("http://127.0.0.1:{port}/gone.xml")).unwrap();
+        assert!(
+            fetch_file(&http, &limiter, &gone, "127.0.0.1", 8, &retry, &ctx)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_user_headers_stay_off_off_scope_redirect_targets() {
+        // Which stub got the header, in request order
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let elsewhere = {
+            let seen = Arc::clone(&seen);
+            stub_server::spawn(move |req, _| {
+                let header = req.header("X-Bug-Bounty").map(str::to_string);
+                seen.lock().unwrap().push(("off-scope", header));
+                response("200 OK", &[], "landed")
+            })
+        };
+        let target = {
+            let seen = Arc::clone(&seen);
+            stub_server::spawn(move |req, _| {
+                let header = req.header("X-Bug-Bounty").map(str::to_string);
+                seen.lock().unwrap().push(("target", header));
+                let next = format!("http://127.0.0.1:{elsewhere}/landing");
+                response("302 Found", &[("Location", &next)], "")
+            })
+        };
+
+        let http = HttpClient::new(&HttpSettings {
+            headers: vec![("X-Bug-Bounty".to_string(), "researcher42".to_string())],
+            ..Default::default()
+        })
+        .unwrap();
+        let limiter = RateLimiter::new(RateLimitSettings::default());
+        let retry = RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        };
+        let start = Url::parse(&format!("http://localhost:{target}/")).unwrap();
+
+        let page = fetch_page(
+            &http,
+            &limiter,
+            &start,
+            "localhost",
+            &retry,
+            &SourceContext::new(),
+        )
+        .unwrap();
+        assert_eq!(page.body, "landed");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("target", Some("researcher42".to_string())),
+                ("off-scope", None),
+            ]
+        );
+    }
 }
@@ -0,0 +1,211 @@
+// src/headers.rs
+
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use url::Url;
+
+use crate::parse::{extract_csp_links, parse_meta_refresh};
+
+/// A URL found in a response header.
+#[derive(Debug, Clone)]
+pub struct HeaderLink {
+    pub url: Url,
+
+    /// Lowercase header name, e.g. "location"
+    pub header: &'static str,
+}
+
+impl HeaderLink {
+    /// Short description of the origin, e.g. "header:location".
+    pub fn via(&self) -> String {
+        format!("header:{}", self.header)
+    }
+}
+
+/// Extract URLs and hostnames leaking through response headers: redirects
+/// (`Location`, `Content-Location`, `Refresh`), `Link`, CORS origins, both CSP
+/// headers, reporting endpoints (`Report-To`, `NEL`), cookie `Domain`
+/// attributes, `Alt-Svc` authorities and `X-Frame-Options: ALLOW-FROM`.
+///
+/// Relative URLs are resolved against `base`. Nothing is filtered by scope.
+pub fn extract_header_links(headers: &HeaderMap, base: &Url) -> Vec<HeaderLink> {
+    let mut out = Vec::new();
+
+    for (header, extract) in EXTRACTORS {
+        for value in headers.get_all(*header) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for url in extract(value, base) {
+                out.push(HeaderLink { url, header });
+            }
+        }
+    }
+
+    out
+}
+
+type Extractor = fn(&str, &Url) -> Vec<Url>;
+
+/// Header name (lowercase) and how to get URLs out of its value.
+const EXTRACTORS: &[(&str, Extractor)] = &[
+    ("location", |v, base| join(base, v).into_iter().collect()),
+    ("content-location", |v, base| {
+        join(base, v).into_iter().collect()
+    }),
+    ("refresh", |v, base| {
+        parse_meta_refresh(v)
+            .and_then(|target| join(base, target))
+            .into_iter()
+            .collect()
+    }),
+    ("link", link_targets),
+    ("access-control-allow-origin", |v, _| {
+        http_url(v.trim()).into_iter().collect()
+    }),
+    ("content-security-policy", |v, _| extract_csp_links(v)),
+    ("content-security-policy-report-only", |v, _| {
+        extract_csp_links(v)
+    }),
+    ("report-to", json_urls),
+    ("nel", json_urls),
+    ("set-cookie", cookie_domain),
+    ("alt-svc", alt_svc_hosts),
+    ("x-frame-options", |v, _| {
+        let v = v.trim();
+        match v.get(..10) {
+            Some(directive) if directive.eq_ignore_ascii_case("allow-from") => {
+                http_url(v[10..].trim()).into_iter().collect()
+            }
+            _ => Vec::new(),
+        }
+    }),
+];
+
+fn join(base: &Url, value: &str) -> Option<Url> {
+    base.join(value.trim())
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+fn http_url(value: &str) -> Option<Url> {
+    Url::parse(value)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+}
+
+/// `<https://cdn.example.com/app.css>; rel=preload, </next>; rel=next`
+fn link_targets(value: &str, base: &Url) -> Vec<Url> {
+    value
+        .split('<')
+        .skip(1)
+        .filter_map(|part| part.split_once('>'))
+        .filter_map(|(target, _)| join(base, target))
+        .collect()
+}
+
+/// Every http(s) URL anywhere in a JSON header (Report-To may hold several
+/// comma-separated objects, so the value is parsed as an array).
+fn json_urls(value: &str, _: &Url) -> Vec<Url> {
+    fn walk(v: &Value, out: &mut Vec<Url>) {
+        match v {
+            Value::String(s) => out.extend(http_url(s)),
+            Value::Array(items) => items.iter().for_each(|i| walk(i, out)),
+            Value::Object(map) => map.values().for_each(|i| walk(i, out)),
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    if let Ok(parsed) = serde_json::from_str::<Value>(&format!("[{}]", value)) {
+        walk(&parsed, &mut out);
+    }
+    out
+}
+
+/// `session=abc; Domain=.example.com; Path=/` -> https://example.com/
+fn cookie_domain(value: &str, _: &Url) -> Vec<Url> {
+    value
+        .split(';')
+        .filter_map(|attr| attr.split_once('='))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("domain"))
+        .filter_map(|(_, domain)| {
+            let domain = domain.trim().trim_start_matches('.');
+            http_url(&format!("https://{}/", domain))
+        })
+        .collect()
+}
+
+/// `h3=":443"; ma=86400, h2="alt.example.com:443"` -> https://alt.example.com/
+fn alt_svc_hosts(value: &str, _: &Url) -> Vec<Url> {
+    value
+        .split(',')
+        .filter_map(|service| service.split_once('='))
+        .filter_map(|(_, rest)| {
+            let authority = rest.split(';').next()?.trim().trim_matches('"');
+            let host = authority.rsplit_once(':').map_or(authority, |(h, _)| h);
+            if host.is_empty() {
+                return None;
+            }
+            http_url(&format!("https://{}/", host))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_extract_header_links() {
+        let mut headers = HeaderMap::new();
+        let mut add = |name: &'static str, value: &'static str| {
+            headers.append(name, HeaderValue::from_static(value));
+        };
+        add("location", "/login");
+        add(
+            "link",
+            "<https://cdn.example.com/app.css>; rel=preload, </next>; rel=next",
+        );
+        add("access-control-allow-origin", "https://app.example.com");
+        add(
+            "content-security-policy-report-only",
+            "script-src 'self' js.example.com",
+        );
+        add(
+            "report-to",
+            r#"{"group":"csp","endpoints":[{"url":"https://reports.example.com/csp"}]}"#,
+        );
+        add(
+            "set-cookie",
+            "sid=1; Path=/; Domain=.auth.example.com; Secure",
+        );
+        add(
+            "alt-svc",
+            r#"h3=":443"; ma=86400, h2="alt.example.com:443""#,
+        );
+        add("x-frame-options", "ALLOW-FROM https://portal.example.com/");
+
+        let base = Url::parse("https://www.example.com/page").unwrap();
+        let found: Vec<(String, String)> = extract_header_links(&headers, &base)
+            .iter()
+            .map(|l| (l.via(), l.url.host_str().unwrap().to_string()))
+            .collect();
+        let has = |via: &str, host: &str| found.contains(&(via.to_string(), host.to_string()));
+
+        assert!(has("header:location", "www.example.com"));
+        assert!(has("header:link", "cdn.example.com"));
+        assert!(has("header:access-control-allow-origin", "app.example.com"));
+        assert!(has(
+            "header:content-security-policy-report-only",
+            "js.example.com"
+        ));
+        assert!(has("header:report-to", "reports.example.com"));
+        assert!(has("header:set-cookie", "auth.example.com"));
+        assert!(has("header:alt-svc", "alt.example.com"));
+        assert!(has("header:x-frame-options", "portal.example.com"));
+        assert_eq!(found.len(), 9);
+    }
+}
@@ -0,0 +1,384 @@
+// src/http.rs
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use url::Url;
+
+use crate::proxy::ProxyMode;
+use crate::subdomains::host_in_scope;
+
+pub const DEFAULT_USER_AGENT: &str = "subrapid-knightchaser/0.1";
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Knobs for the shared HTTP client.
+#[derive(Clone, Debug)]
+pub struct HttpSettings {
+    /// Per-request timeout (sources may override it per request)
+    pub timeout: Duration,
+    pub user_agent: String,
+
+    /// Extra headers, like cookies only sent to hosts inside the target's root domain
+    pub headers: Vec<(String, String)>,
+
+    /// Cookies ("name=value"), only ever sent to hosts inside the target's root domain
+    pub cookies: Vec<String>,
+
+    /// Where requests go: direct or through an upstream proxy (which may come
+    /// from the environment)
+    pub proxy: ProxyMode,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: Vec::new(),
+            cookies: Vec::new(),
+            proxy: ProxyMode::Direct,
+        }
+    }
+}
+
+/// A pooled HTTP client shared by the crawler workers and every source.
+///
+/// Cloning is cheap and keeps the same connection pool.
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    inner: Client,
+
+    /// Same settings, but redirects are handed back instead of followed
+    manual: Client,
+
+    /// User headers and cookie, attached to requests to the target only
+    headers: HeaderMap,
+    cookie: Option<HeaderValue>,
+    proxy: ProxyMode,
+    timeout: Duration,
+}
+
+impl HttpClient {
+    pub fn new(settings: &HttpSettings) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &settings.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("invalid header name: {}", name))?;
+            let value = HeaderValue::from_str(value)
+                .with_context(|| format!("invalid value for header {}", name))?;
+            headers.append(name, value);
+        }
+
+        let cookie = if settings.cookies.is_empty() {
+            None
+        } else {
+            let joined = settings.cookies.join("; ");
+            Some(HeaderValue::from_str(&joined).context("invalid cookie value")?)
+        };
+
+        Ok(Self {
+            inner: build_client(settings, Policy::default())?,
+            manual: build_client(settings, Policy::none())?,
+            headers,
+            cookie,
+            proxy: settings.proxy.clone(),
+            timeout: settings.timeout,
+        })
+    }
+
+    /// The proxy setting this client was built with.
+    pub fn proxy(&self) -> &ProxyMode {
+        &self.proxy
+    }
+
+    /// The per-request timeout this client was built with (`--timeout`).
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Start a GET request to a third party (no user headers or cookies attached).
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.inner.get(url)
+    }
+
+    /// Start a GET request to the target itself. User headers and cookies are
+    /// attached only when the host is inside `root_domain`.
+    ///
+    /// A redirect response is returned as is, so the caller follows (and
+    /// records) each hop itself, deciding per hop what to attach.
+    pub fn get_hop(&self, url: &Url, root_domain: &str) -> RequestBuilder {
+        let req = self.manual.get(url.as_str());
+        if !url
+            .host_str()
+            .is_some_and(|host| host_in_scope(host, root_domain))
+        {
+            return req;
+        }
+
+        let req = req.headers(self.headers.clone());
+        match &self.cookie {
+            Some(cookie) => req.header(reqwest::header::COOKIE, cookie.clone()),
+            None => req,
+        }
+    }
+}
+
+fn build_client(settings: &HttpSettings, redirect: Policy) -> Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(settings.timeout)
+        .user_agent(settings.user_agent.as_str())
+        .redirect(redirect);
+
+    match &settings.proxy {
+        ProxyMode::Direct => builder = builder.no_proxy(),
+        ProxyMode::Upstream(upstream) => {
+            let upstream = upstream.clone();
+            let proxy = reqwest::Proxy::custom(move |url| {
+                let host = url.host_str()?;
+                if upstream.bypass.matches(host) {
+                    None
+                } else {
+                    Some(upstream.url.clone())
+                }
+            });
+            builder = builder.proxy(proxy);
+        }
+    }
+
+    builder.build().context("failed to build HTTP client")
+}
+
+/// The shared client plus clients for sources with their own proxy setting.
+#[derive(Clone, Debug)]
+pub struct HttpClients {
+    default: HttpClient,
+    per_source: HashMap<String, HttpClient>,
+}
+
+impl HttpClients {
+    /// Build the shared client from `settings`, and one more client per
+    /// `(source, proxy)` override with everything but the proxy unchanged.
+    pub fn new(settings: &HttpSettings, overrides: &[(String, ProxyMode)]) -> Result<Self> {
+        let default = HttpClient::new(settings)?;
+
+        let mut per_source = HashMap::new();
+        for (source, proxy) in overrides {
+            let client = HttpClient::new(&HttpSettings {
+                proxy: proxy.clone(),
+                ..settings.clone()
+            })?;
+            per_source.insert(source.clone(), client);
+        }
+
+        Ok(Self {
+            default,
+            per_source,
+        })
+    }
+
+    /// The client used by sources without an override.
+    pub fn shared(&self) -> &HttpClient {
+        &self.default
+    }
+
+    /// The client a given source should use.
+    pub fn for_source(&self, name: &str) -> &HttpClient {
+        self.per_source.get(name).unwrap_or(&self.default)
+    }
+}
+
+/// A response with a non-success status.
+///
+/// Kept as a concrete type so callers can `downcast_ref` it out of an
+/// `anyhow::Error` and react to throttling.
+#[derive(Debug)]
+pub struct HttpStatusError {
+    pub status: StatusCode,
+
+    /// Parsed Retry-After header, if the server sent one
+    pub retry_after: Option<Duration>,
+
+    /// Host that answered (lowercase), which after a redirect may not be the
+    /// host first asked
+    pub host: String,
+}
+
+impl HttpStatusError {
+    pub fn from_response(resp: &Response) -> Self {
+        Self {
+            status: resp.status(),
+            host: resp.url().host_str().unwrap_or_default().to_lowercase(),
+            retry_after: resp
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_retry_after),
+        }
+    }
+
+    /// 429 Too Many Requests or 503 Service Unavailable.
+    pub fn is_throttling(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS
+            || self.status == StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request failed with status: {}", self.status)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// Parse a Retry-After value: delay in seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(
+        at.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Parse a "Name: value" header argument.
+pub fn parse_header(s: &str) -> Result<(String, String)> {
+    let (name, value) = s
+        .split_once(':')
+        .with_context(|| format!("header must look like 'Name: value', got: {}", s))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// A minimal HTTP/1.1 server for tests, bound to 127.0.0.1.
+#[cfg(test)]
+pub(crate) mod stub_server {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// What the server saw of a request.
+    pub struct Request {
+        /// Path and query, e.g. "/api?page=2"
+        pub path: String,
+        headers: Vec<(String, String)>,
+    }
+
+    impl Request {
+        pub fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Spawn a server that answers every request with `handler(request, port)`,
+    /// a complete raw response (see `response`). Returns the port.
+    pub fn spawn(handler: impl Fn(&Request, u16) -> String + Send + 'static) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+
+                let mut headers = Vec::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.push((name.trim().to_string(), value.trim().to_string()));
+                    }
+                    line.clear();
+                }
+
+                let request = Request {
+                    path: request_line
+                        .split_whitespace()
+                        .nth(1)
+                        .unwrap_or("/")
+                        .to_string(),
+                    headers,
+                };
+                let _ = stream.write_all(handler(&request, port).as_bytes());
+            }
+        });
+
+        port
+    }
+
+    /// A raw response with the given status line ("200 OK"), extra headers and body.
+    /// The connection is closed after each response.
+    pub fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+        let mut out = format!("HTTP/1.1 {}\r\n", status);
+        for (name, value) in headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        out.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookies_and_headers_stay_in_scope() {
+        let client = HttpClient::new(&HttpSettings {
+            cookies: vec!["session=abc".to_string()],
+            headers: vec![("Authorization".to_string(), "Bearer t0ken".to_string())],
+            ..HttpSettings::default()
+        })
+        .unwrap();
+
+        let in_scope = Url::parse("https://app.example.com/").unwrap();
+        let req = client.get_hop(&in_scope, "example.com").build().unwrap();
+        assert_eq!(req.headers()["cookie"], "session=abc");
+        assert_eq!(req.headers()["authorization"], "Bearer t0ken");
+
+        let elsewhere = Url::parse("https://example.com.evil.org/").unwrap();
+        let req = client.get_hop(&elsewhere, "example.com").build().unwrap();
+        assert!(req.headers().get("cookie").is_none());
+        assert!(req.headers().get("authorization").is_none());
+
+        let req = client.get("https://crt.sh/?q=example.com").build().unwrap();
+        assert!(req.headers().get("authorization").is_none());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("X-Bug-Bounty: researcher42").unwrap(),
+            ("X-Bug-Bounty".to_string(), "researcher42".to_string())
+        );
+        assert!(parse_header("no-colon").is_err());
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after(" 120 "), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -0,0 +1,127 @@
+// src/js.rs
+
+use std::collections::HashSet;
+
+use url::Url;
+
+/// Find URLs and hostnames under `root_domain` referenced in JavaScript source.
+///
+/// Picks up absolute URLs ("https://api.example.com/v1"), protocol-relative
+/// URLs ("//cdn.example.com/x.js", assumed HTTPS) and bare hostnames
+/// ("ws.example.com", turned into "https://ws.example.com/"). JSON-style
+/// escaped slashes (`\/`, `/`) are understood. Results are deduplicated.
+pub fn find_references(source: &str, root_domain: &str) -> Vec<Url> {
+    let text = source
+        .replace("\\/", "/")
+        .replace("\\u002F", "/")
+        .replace("\\u002f", "/");
+    // ASCII lowercasing keeps byte offsets identical to `text`
+    let lower = text.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let root = root_domain.to_ascii_lowercase();
+    let suffix = format!(".{}", root);
+
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    let mut from = 0;
+
+    while let Some(pos) = lower[from..].find(&root) {
+        let root_start = from + pos;
+        let end = root_start + root.len();
+        from = end;
+
+        // "example.com.evil.org" or "example.company" are not ours
+        match bytes.get(end) {
+            Some(&c) if is_host_char(c) => continue,
+            Some(b'.')
+                if bytes
+                    .get(end + 1)
+                    .is_some_and(|c| c.is_ascii_alphanumeric()) =>
+            {
+                continue;
+            }
+            _ => {}
+        }
+
+        // Walk left over the subdomain labels
+        let mut start = root_start;
+        while start > 0 && (is_host_char(bytes[start - 1]) || bytes[start - 1] == b'.') {
+            start -= 1;
+        }
+        let host = lower[start..end].trim_start_matches(['.', '-']);
+        let start = end - host.len();
+        if (host != root && !host.ends_with(&suffix)) || host.contains("..") {
+            continue;
+        }
+
+        let before = &lower[..start];
+        let url_start = ["https://", "http://", "//"]
+            .iter()
+            .find(|scheme| before.ends_with(*scheme))
+            .map_or(start, |scheme| start - scheme.len());
+
+        let candidate = if url_start == start {
+            format!("https://{}/", host)
+        } else {
+            let mut url_end = end;
+            while url_end < bytes.len() && is_url_char(bytes[url_end]) {
+                url_end += 1;
+            }
+            let raw = &text[url_start..url_end];
+            if raw.starts_with("//") {
+                format!("https:{}", raw)
+            } else {
+                raw.to_string()
+            }
+        };
+
+        if let Ok(url) = Url::parse(&candidate)
+            && seen.insert(url.to_string())
+        {
+            out.push(url);
+        }
+    }
+
+    out
+}
+
+fn is_host_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-'
+}
+
+/// Characters that can continue a URL inside a JS string or comment.
+fn is_url_char(c: u8) -> bool {
+    c.is_ascii_graphic() && !b"\"'`<>()[]{}\\,;$|^".contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_references() {
+        let js = r#"
+            const API = "https://api.example.com/v2/users?id=1";
+            fetch(`//cdn.Example.com/assets/${name}`);
+            var cfg = {"ws":"wss:\/\/ws.example.com\/socket","auth":"sso.example.com"};
+            // not ours: example.com.evil.org, notexample.com, example.company
+            location.href = 'http://example.com:8080/login';
+        "#;
+
+        let found: Vec<String> = find_references(js, "example.com")
+            .iter()
+            .map(Url::to_string)
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "https://api.example.com/v2/users?id=1",
+                "https://cdn.example.com/assets/",
+                "https://ws.example.com/socket",
+                "https://sso.example.com/",
+                "http://example.com:8080/login",
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 // src/logging.rs
 
+use std::time::Duration;
+
 use colored::Colorize;
 use url::Url;
 
@@ -34,6 +36,22 @@ pub fn log_worker_error(worker_id: usize, url: &Url, err: &anyhow::Error, stats:
     );
 }
 
+pub fn log_worker_throttled(worker_id: usize, url: &Url, delay: Duration, stats: &CrawlerStats) {
+    eprintln!(
+        "{} {} Throttled on {}, backing off {}s",
+        "[!]".yellow().bold(),
+        format!(
+            "[worker {} ({} visited, max {} possible)]",
+            worker_id,
+            stats.visited_pages,
+            stats.max_possible_pages()
+        )
+        .yellow(),
+        url,
+        delay.as_secs_f64()
+    );
+}
+
 pub fn log_worker_finished(worker_id: usize, url: &Url, stats: &CrawlerStats) {
     eprintln!(
         "{} {} Finished {}",
@@ -1,83 +1,302 @@
 // src/main.rs
 
 mod cli;
+mod ctlog;
+mod dns;
 mod fetch;
+mod headers;
+mod http;
+mod js;
 mod logging;
+mod orchestrator;
+mod output;
 mod parse;
+mod proxy;
+mod ratelimit;
+mod retry;
+mod sitemap;
+mod sourcemap;
 mod sources;
 mod subdomains;
+mod summary;
+mod targets;
+mod tls;
+mod wildcard;
+
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use clap::Parser;
 use colored::Colorize;
-use url::Url;
 
 use crate::cli::Cli;
-use crate::sources::crtsh::CrtSh;
-use crate::sources::html_crawler::HtmlCrawler;
-use crate::sources::wayback::WaybackArchive;
-use crate::sources::{DiscoveryConfig, SubdomainSource};
-use crate::subdomains::{SubdomainMap, extract_root_domain};
+use crate::http::{HttpClients, HttpSettings};
+use crate::orchestrator::Timeouts;
+use crate::output::Report;
+use crate::proxy::{BypassList, ProxyMode};
+use crate::ratelimit::RateLimitSettings;
+use crate::retry::RetryPolicy;
+use crate::sources::DiscoveryConfig;
+use crate::sources::dns_bruteforce::parse_wordlist;
+use crate::sources::registry::{self, SourceOptions};
+use crate::targets::{Target, read_target_lines};
+use crate::wildcard::filter_wildcards;
+
+/// Wall-clock limit per source unless overridden with --source-timeout.
+const DEFAULT_SOURCE_TIMEOUT: Duration = Duration::from_secs(600);
 
 fn main() -> Result<()> {
     let args = Cli::parse();
 
-    let start_url =
-        Url::parse(&args.url).with_context(|| format!("invalid start URL: {}", args.url))?;
-
-    let host = start_url
-        .host_str()
-        .with_context(|| {
-            format!(
-                "Cannot derive root domain from URL {} without host",
-                args.url
-            )
-        })?
-        .to_lowercase();
-
-    let root_domain = if let Some(rd) = args.root_domain {
-        rd.to_lowercase()
-    } else if let Some(root) = extract_root_domain(&host) {
-        root
-    } else {
-        return Err(anyhow::anyhow!(
-            "Cannot derive root domain from host: {}. Please specify --root-domain",
-            host
-        ));
+    // Keep ANSI escapes out of pipes and redirected output
+    if !io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+
+    if args.list_sources {
+        for spec in registry::REGISTRY {
+            let kind = if spec.passive { "passive" } else { "active" };
+            println!("{:<16} {:<8} {}", spec.id, kind, spec.description);
+        }
+        return Ok(());
+    }
+
+    let inputs = collect_target_inputs(&args)?;
+    if inputs.len() > 1 && args.root_domain.is_some() {
+        anyhow::bail!("--root-domain can only be combined with a single target");
+    }
+    let targets = inputs
+        .iter()
+        .map(|input| Target::parse(input, args.root_domain.as_deref()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let resolvers = args
+        .resolvers
+        .iter()
+        .map(|r| dns::parse_resolver(r))
+        .collect::<Result<Vec<_>>>()?;
+
+    let bypass = match &args.no_proxy {
+        Some(list) => BypassList::parse(list)?,
+        None => BypassList::from_env()?,
     };
+    let proxy = match &args.proxy {
+        Some(p) => ProxyMode::parse(p, &bypass)?,
+        None => ProxyMode::from_env(&bypass)?,
+    };
+    let source_proxies = args
+        .source_proxies
+        .iter()
+        .map(|spec| proxy::parse_source_proxy(spec, &bypass))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some((id, _)) = source_proxies
+        .iter()
+        .find(|(id, _)| registry::find(id).is_none())
+    {
+        anyhow::bail!("unknown source '{}' in --source-proxy", id);
+    }
 
-    let cfg = DiscoveryConfig {
-        start_url: start_url.clone(),
-        root_domain: root_domain.clone(),
-        workers: args.workers,
-        max_pages_per_host: args.max_pages_per_host,
+    let settings = HttpSettings {
+        timeout: Duration::from_secs(args.timeout),
+        user_agent: args.user_agent.clone(),
+        headers: args
+            .headers
+            .iter()
+            .map(|h| http::parse_header(h))
+            .collect::<Result<Vec<_>>>()?,
+        cookies: args.cookies.clone(),
+        proxy,
     };
+    let clients = HttpClients::new(&settings, &source_proxies)?;
 
-    let sources: Vec<Box<dyn SubdomainSource>> = vec![
-        Box::new(HtmlCrawler::new()),
-        Box::new(CrtSh::new()),
-        Box::new(WaybackArchive::new()),
-    ];
-
-    let mut combined = SubdomainMap::new();
-    for src in sources {
-        eprintln!(
-            "{}",
-            format!("[*] Running source: {}", src.name())
+    let wordlist = match &args.wordlist {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read wordlist {}", path.display()))?;
+            Some(parse_wordlist(&text))
+        }
+        None => None,
+    };
+    let source_opts = SourceOptions {
+        wordlist,
+        tls_ports: args.tls_ports.clone(),
+        ct_logs: args.ct_logs.clone(),
+        ct_checkpoint: args.ct_checkpoint.clone(),
+        ct_max_entries: args.ct_max_entries,
+        wayback_all_paths: args.wayback_all_paths,
+        commoncrawl_indexes: args.commoncrawl_indexes,
+        base_urls: args
+            .source_base_urls
+            .iter()
+            .map(|spec| registry::parse_source_setting(spec, "--source-base-url"))
+            .collect::<Result<_>>()?,
+        api_keys: args
+            .api_keys
+            .iter()
+            .map(|spec| registry::parse_source_setting(spec, "--api-key"))
+            .collect::<Result<_>>()?,
+    };
+
+    let specs = registry::select(
+        &args.sources,
+        &args.exclude_sources,
+        args.passive,
+        &source_opts,
+    )?;
+    if args
+        .rate_limit
+        .is_some_and(|rps| !(rps > 0.0 && rps.is_finite()))
+    {
+        anyhow::bail!("--rate-limit must be a positive number of requests per second");
+    }
+    let rate_limit = RateLimitSettings {
+        requests_per_second: args.rate_limit,
+        per_host_delay: Duration::from_millis(args.host_delay),
+    };
+
+    let timeouts = Timeouts::parse(&args.source_timeout, DEFAULT_SOURCE_TIMEOUT)?;
+
+    let mut results = Vec::new();
+    for target in &targets {
+        if targets.len() > 1 {
+            eprintln!(
+                "{}",
+                format!(
+                    "[*] Scanning target {} (root domain {})",
+                    target.input, target.root_domain
+                )
                 .magenta()
                 .bold()
-        );
-        let map = src.discover(&cfg)?;
-        combined.merge_from(map);
+            );
+        }
+
+        let cfg = DiscoveryConfig {
+            start_url: target.start_url.clone(),
+            root_domain: target.root_domain.clone(),
+            workers: args.workers,
+            max_pages_per_host: args.max_pages_per_host,
+            max_scripts_per_host: args.max_scripts_per_host,
+            harvest_sitemaps: !args.no_sitemaps,
+            rate_limit,
+            retry: RetryPolicy {
+                max_attempts: args.retries + 1,
+                ..RetryPolicy::default()
+            },
+            resolvers: resolvers.clone(),
+            seed_hosts: Vec::new(),
+            passive: args.passive,
+            http: clients.shared().clone(),
+        };
+
+        // Follow-up sources probe what the others found, so they start last
+        let (first, follow_up): (Vec<_>, Vec<_>) =
+            specs.iter().copied().partition(|spec| !spec.follow_up);
+        let build = |specs: &[&registry::SourceSpec]| {
+            specs
+                .iter()
+                .map(|spec| spec.build(&source_opts))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let (mut combined, mut outcomes) =
+            orchestrator::run_sources(build(&first)?, &cfg, &clients, &timeouts);
+
+        // Only resolver-based sources attach DNS answers, so this is a no-op otherwise.
+        // It runs before the follow-up sources so they never probe wildcard junk.
+        // Passive runs never probe: the queries would reach the target's name servers.
+        // Probes follow the same proxy rules as the bruteforce that produced the answers.
+        let dns_proxy = clients.for_source("dns-bruteforce").proxy();
+        let wildcard_matches = if cfg.passive
+            || combined.resolved_hosts().is_empty()
+            || dns::ensure_unproxied(&cfg.resolvers, dns_proxy, "dns-bruteforce").is_err()
+        {
+            Vec::new()
+        } else {
+            let resolver = dns::Resolver::new(cfg.resolvers.clone(), dns::DEFAULT_TIMEOUT);
+            filter_wildcards(&mut combined, &cfg.root_domain, &resolver)
+        };
+
+        if !follow_up.is_empty() {
+            let cfg = DiscoveryConfig {
+                seed_hosts: combined.hosts().into_iter().map(str::to_string).collect(),
+                ..cfg.clone()
+            };
+            let (found, more) =
+                orchestrator::run_sources(build(&follow_up)?, &cfg, &clients, &timeouts);
+            combined.merge_from(found);
+            outcomes.extend(more);
+        }
+
+        results.push((combined, wildcard_matches, outcomes));
+    }
+
+    let reports: Vec<Report> = targets
+        .iter()
+        .zip(&results)
+        .map(|(target, (map, wildcard_matches, outcomes))| Report {
+            target: &target.input,
+            root_domain: &target.root_domain,
+            map,
+            wildcard_matches,
+            sources: outcomes,
+        })
+        .collect();
+
+    let (mut out, color): (Box<dyn Write>, bool) = match &args.output {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("failed to create output file {}", path.display()))?;
+            (Box::new(BufWriter::new(file)), false)
+        }
+        None => (Box::new(io::stdout().lock()), io::stdout().is_terminal()),
+    };
+    output::write_reports(&mut out, args.output_format, &reports, color)?;
+
+    for report in &reports {
+        summary::print_summary(report.target, report.sources);
     }
 
-    println!(
-        "{}",
-        format!("Discovered subdomains under '{}':", root_domain)
-            .green()
-            .bold()
-    );
-    combined.print_subdomains_only(&root_domain);
+    let failed = reports
+        .iter()
+        .flat_map(|r| r.sources)
+        .filter(|o| o.is_failed())
+        .count();
+    if failed > 0 && args.fail_on_source_error {
+        anyhow::bail!("{} source(s) failed", failed);
+    }
 
     Ok(())
 }
+
+/// Gather raw target strings from the positional URL (or `-` for stdin),
+/// `--targets-file`, or a bare `--root-domain`.
+fn collect_target_inputs(args: &Cli) -> Result<Vec<String>> {
+    let mut inputs = Vec::new();
+
+    match args.url.as_deref() {
+        Some("-") => inputs.extend(read_target_lines(io::stdin().lock())?),
+        Some(url) => inputs.push(url.to_string()),
+        None => {}
+    }
+
+    if let Some(path) = &args.targets_file {
+        let file = File::open(path)
+            .with_context(|| format!("failed to open targets file {}", path.display()))?;
+        inputs.extend(read_target_lines(io::BufReader::new(file))?);
+    }
+
+    if inputs.is_empty() && args.url.is_none() && args.targets_file.is_none() {
+        // clap guarantees --root-domain is present here
+        if let Some(root) = &args.root_domain {
+            inputs.push(root.clone());
+        }
+    }
+
+    if inputs.is_empty() {
+        anyhow::bail!("no targets given");
+    }
+
+    Ok(inputs)
+}
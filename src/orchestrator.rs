@@ -0,0 +1,336 @@
+// src/orchestrator.rs
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::http::HttpClients;
+use crate::sources::registry;
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+use crate::summary::{self, SourceOutcome, SourceStatus};
+
+/// How long a timed-out source gets to hand back partial results after being cancelled.
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// Wall-clock limits for sources: one default plus optional per-source overrides.
+#[derive(Clone, Debug)]
+pub struct Timeouts {
+    default: Duration,
+    per_source: HashMap<String, Duration>,
+}
+
+impl Timeouts {
+    /// Parse `--source-timeout` values: a bare number of seconds sets the default,
+    /// `NAME=SECS` overrides the limit for a single source, which must exist.
+    pub fn parse(specs: &[String], default: Duration) -> Result<Self> {
+        let mut timeouts = Self {
+            default,
+            per_source: HashMap::new(),
+        };
+
+        for spec in specs {
+            let (name, secs) = match spec.rsplit_once('=') {
+                Some((name, secs)) => (Some(name.trim()), secs),
+                None => (None, spec.as_str()),
+            };
+            let secs: u64 = secs
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid source timeout: {}", spec))?;
+
+            match name {
+                Some(name) if registry::find(name).is_none() => {
+                    anyhow::bail!("unknown source '{}' in --source-timeout", name);
+                }
+                Some(name) => {
+                    timeouts
+                        .per_source
+                        .insert(name.to_string(), Duration::from_secs(secs));
+                }
+                None => timeouts.default = Duration::from_secs(secs),
+            }
+        }
+
+        Ok(timeouts)
+    }
+
+    pub fn for_source(&self, name: &str) -> Duration {
+        self.per_source.get(name).copied().unwrap_or(self.default)
+    }
+}
+
+/// Bookkeeping for a source that has not reported back yet.
+struct Pending {
+    name: &'static str,
+    ctx: Arc<SourceContext>,
+    timeout: Duration,
+    deadline: Instant,
+    cancelled: bool,
+}
+
+/// Run all sources in parallel, each on its own thread with its own deadline.
+///
+/// Maps are merged into the returned map as sources complete. A source that
+/// overruns its deadline is cancelled and given a short grace period to return
+/// what it has; after that it is reported as failed and left behind.
+/// Each source gets the HTTP client `clients` holds for it, so per-source
+/// proxy overrides apply. Outcomes are returned in the same order as `sources`.
+pub fn run_sources(
+    sources: Vec<Box<dyn SubdomainSource>>,
+    cfg: &DiscoveryConfig,
+    clients: &HttpClients,
+    timeouts: &Timeouts,
+) -> (SubdomainMap, Vec<SourceOutcome>) {
+    let (tx, rx) = mpsc::channel();
+    let mut pending: HashMap<usize, Pending> = HashMap::new();
+    let mut outcomes: Vec<Option<SourceOutcome>> = Vec::new();
+
+    for (idx, src) in sources.into_iter().enumerate() {
+        let name = src.name();
+
+        // Last line of defence: the registry should never hand us these.
+        if cfg.passive && !src.is_passive() {
+            let outcome = SourceOutcome {
+                source: name,
+                status: SourceStatus::Failed {
+                    reason: "refused to start an active source in passive mode".to_string(),
+                },
+                hosts_found: 0,
+                attempts: 0,
+                retries: 0,
+                quota: None,
+                api_requests: 0,
+            };
+            log_finished(&outcome);
+            outcomes.push(Some(outcome));
+            continue;
+        }
+
+        let timeout = timeouts.for_source(name);
+        let ctx = Arc::new(SourceContext::new());
+
+        eprintln!(
+            "{}",
+            format!(
+                "[*] Running source: {} (timeout {}s)",
+                name,
+                timeout.as_secs()
+            )
+            .magenta()
+            .bold()
+        );
+
+        let tx = tx.clone();
+        let mut cfg = cfg.clone();
+        cfg.http = clients.for_source(name).clone();
+        let thread_ctx = Arc::clone(&ctx);
+        thread::spawn(move || {
+            let result = summary::run_isolated(src.as_ref(), &cfg, &thread_ctx);
+            // The receiver is gone if the orchestrator already gave up on us
+            let _ = tx.send((idx, result));
+        });
+
+        pending.insert(
+            idx,
+            Pending {
+                name,
+                ctx,
+                timeout,
+                deadline: Instant::now() + timeout,
+                cancelled: false,
+            },
+        );
+        outcomes.push(None);
+    }
+    drop(tx);
+
+    let mut combined = SubdomainMap::new();
+
+    while !pending.is_empty() {
+        let next_deadline = pending.values().map(|p| p.deadline).min().unwrap();
+        let wait = next_deadline.saturating_duration_since(Instant::now());
+
+        match rx.recv_timeout(wait) {
+            Ok((idx, (map, mut outcome))) => {
+                let Some(p) = pending.remove(&idx) else {
+                    continue;
+                };
+
+                if p.cancelled {
+                    mark_timed_out(&mut outcome, p.timeout);
+                }
+                log_finished(&outcome);
+
+                combined.merge_from(map);
+                outcomes[idx] = Some(outcome);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let now = Instant::now();
+                let expired: Vec<usize> = pending
+                    .iter()
+                    .filter(|(_, p)| p.deadline <= now)
+                    .map(|(idx, _)| *idx)
+                    .collect();
+
+                for idx in expired {
+                    let p = pending.get_mut(&idx).unwrap();
+                    if !p.cancelled {
+                        eprintln!(
+                            "{} Source {} exceeded its {}s timeout, cancelling",
+                            "[!]".red().bold(),
+                            p.name,
+                            p.timeout.as_secs()
+                        );
+                        p.ctx.cancel();
+                        p.cancelled = true;
+                        p.deadline = now + CANCEL_GRACE;
+                        continue;
+                    }
+
+                    // Grace period is over as well; stop waiting for it.
+                    let p = pending.remove(&idx).unwrap();
+                    let outcome = SourceOutcome {
+                        source: p.name,
+                        status: SourceStatus::Failed {
+                            reason: format!(
+                                "timed out after {}s and did not stop when cancelled",
+                                p.timeout.as_secs()
+                            ),
+                        },
+                        hosts_found: 0,
+                        attempts: p.ctx.attempts(),
+                        retries: p.ctx.retries(),
+                        quota: p.ctx.quota(),
+                        api_requests: p.ctx.api_requests(),
+                    };
+                    log_finished(&outcome);
+                    outcomes[idx] = Some(outcome);
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    (combined, outcomes.into_iter().flatten().collect())
+}
+
+/// A source that returned after being cancelled only has partial results.
+fn mark_timed_out(outcome: &mut SourceOutcome, timeout: Duration) {
+    let reason = format!(
+        "timed out after {}s, results are incomplete",
+        timeout.as_secs()
+    );
+
+    match &mut outcome.status {
+        SourceStatus::Ok => {
+            outcome.status = SourceStatus::Partial {
+                reasons: vec![reason],
+            };
+        }
+        SourceStatus::Partial { reasons } => reasons.insert(0, reason),
+        SourceStatus::Failed { .. } => {}
+    }
+}
+
+fn log_finished(outcome: &SourceOutcome) {
+    match &outcome.status {
+        SourceStatus::Failed { reason } => {
+            eprintln!(
+                "{} Source {} failed: {}",
+                "[!]".red().bold(),
+                outcome.source,
+                reason
+            );
+        }
+        _ => {
+            eprintln!(
+                "{}",
+                format!(
+                    "[*] Source {} finished with {} host(s)",
+                    outcome.source, outcome.hosts_found
+                )
+                .magenta()
+                .bold()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sleeps in small steps until cancelled, then returns what it "found".
+    struct Slow;
+
+    impl SubdomainSource for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+            let mut map = SubdomainMap::new();
+            map.add_url(&cfg.start_url, &cfg.root_domain, self.name());
+            while !ctx.is_cancelled() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Ok(map)
+        }
+    }
+
+    struct Quick;
+
+    impl SubdomainSource for Quick {
+        fn name(&self) -> &'static str {
+            "quick"
+        }
+
+        fn discover(&self, cfg: &DiscoveryConfig, _ctx: &SourceContext) -> Result<SubdomainMap> {
+            let mut map = SubdomainMap::new();
+            let url = cfg.start_url.join("//api.example.com/").unwrap();
+            map.add_url(&url, &cfg.root_domain, self.name());
+            Ok(map)
+        }
+    }
+
+    #[test]
+    fn test_parse_timeouts_checks_source_names() {
+        let specs = ["30".to_string(), "crtsh=60".to_string()];
+        let timeouts = Timeouts::parse(&specs, Duration::ZERO).unwrap();
+        assert_eq!(timeouts.for_source("crtsh"), Duration::from_secs(60));
+        assert_eq!(timeouts.for_source("wayback"), Duration::from_secs(30));
+
+        let err = Timeouts::parse(&["crt.sh=60".to_string()], Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("unknown source 'crt.sh'"));
+    }
+
+    #[test]
+    fn test_timeout_cancels_and_keeps_partial_results() {
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        // Test sources aren't registered, so `parse` would refuse "slow=0"
+        let timeouts = Timeouts {
+            default: Duration::from_secs(60),
+            per_source: HashMap::from([("slow".to_string(), Duration::ZERO)]),
+        };
+
+        let clients = HttpClients::new(&Default::default(), &[]).unwrap();
+
+        let (map, outcomes) = run_sources(
+            vec![Box::new(Slow), Box::new(Quick)],
+            &cfg,
+            &clients,
+            &timeouts,
+        );
+
+        assert_eq!(map.hosts(), vec!["api.example.com", "www.example.com"]);
+        assert_eq!(outcomes[0].source, "slow");
+        assert!(matches!(outcomes[0].status, SourceStatus::Partial { .. }));
+        assert!(matches!(outcomes[1].status, SourceStatus::Ok));
+    }
+}
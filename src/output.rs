@@ -0,0 +1,313 @@
+// src/output.rs
+
+use std::io::{self, Write};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::subdomains::{SubdomainEntry, SubdomainMap};
+use crate::summary::SourceOutcome;
+use crate::wildcard::WildcardMatch;
+
+/// How results are written out.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Subdomain names only, one per line
+    Plain,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// Final results for one target.
+pub struct Report<'a> {
+    /// The target as given on the command line or in the targets file
+    pub target: &'a str,
+    pub root_domain: &'a str,
+    pub map: &'a SubdomainMap,
+    pub wildcard_matches: &'a [WildcardMatch],
+    pub sources: &'a [SourceOutcome],
+}
+
+#[derive(Serialize)]
+struct HostRecord<'a> {
+    host: &'a str,
+    #[serde(flatten)]
+    entry: &'a SubdomainEntry,
+}
+
+impl Report<'_> {
+    fn host_records(&self) -> Vec<HostRecord<'_>> {
+        self.map
+            .entries()
+            .into_iter()
+            .map(|(host, entry)| HostRecord { host, entry })
+            .collect()
+    }
+}
+
+/// Write the reports, grouped per target, in the requested format.
+/// `color` only affects the plain format; the others never contain ANSI codes.
+pub fn write_reports(
+    out: &mut dyn Write,
+    format: OutputFormat,
+    reports: &[Report],
+    color: bool,
+) -> Result<()> {
+    match format {
+        OutputFormat::Plain => {
+            for (i, report) in reports.iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                write_plain(out, report, reports.len() > 1, color)?;
+            }
+        }
+        OutputFormat::Json => write_json(out, reports)?,
+        OutputFormat::Jsonl => {
+            for report in reports {
+                write_jsonl(out, report)?;
+            }
+        }
+        OutputFormat::Csv => {
+            write_csv_header(out)?;
+            for report in reports {
+                write_csv(out, report)?;
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+fn write_plain(
+    out: &mut dyn Write,
+    report: &Report,
+    show_target: bool,
+    color: bool,
+) -> io::Result<()> {
+    let heading = if show_target {
+        format!(
+            "Discovered subdomains under '{}' (target {}):",
+            report.root_domain, report.target
+        )
+    } else {
+        format!("Discovered subdomains under '{}':", report.root_domain)
+    };
+    if color {
+        writeln!(out, "{}", heading.green().bold())?;
+    } else {
+        writeln!(out, "{}", heading)?;
+    }
+    report
+        .map
+        .write_subdomains_only(out, report.root_domain, color)?;
+
+    if report.wildcard_matches.is_empty() {
+        return Ok(());
+    }
+
+    let heading = format!(
+        "Suppressed {} wildcard DNS match(es) under '{}':",
+        report.wildcard_matches.len(),
+        report.root_domain
+    );
+    if color {
+        writeln!(out, "{}", heading.yellow().bold())?;
+    } else {
+        writeln!(out, "{}", heading)?;
+    }
+    for m in report.wildcard_matches {
+        let addrs: Vec<String> = m.addrs.iter().map(|a| a.to_string()).collect();
+        writeln!(
+            out,
+            "{} -> {} (matches *.{})",
+            m.host,
+            addrs.join(", "),
+            m.parent
+        )?;
+    }
+
+    Ok(())
+}
+
+/// One document with a `targets` array, even for a single target.
+fn write_json(out: &mut dyn Write, reports: &[Report]) -> Result<()> {
+    let targets: Vec<_> = reports
+        .iter()
+        .map(|report| {
+            json!({
+                "target": report.target,
+                "root_domain": report.root_domain,
+                "subdomains": report.host_records(),
+                "wildcard_matches": report.wildcard_matches,
+                "sources": report.sources,
+            })
+        })
+        .collect();
+    serde_json::to_writer_pretty(&mut *out, &json!({ "targets": targets }))?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Every line carries a `kind` ("subdomain" or "wildcard") so consumers can filter.
+fn write_jsonl(out: &mut dyn Write, report: &Report) -> Result<()> {
+    for record in report.host_records() {
+        let mut line = serde_json::to_value(&record)?;
+        line["kind"] = json!("subdomain");
+        line["target"] = json!(report.target);
+        line["root_domain"] = json!(report.root_domain);
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+
+    for m in report.wildcard_matches {
+        let mut line = serde_json::to_value(m)?;
+        line["kind"] = json!("wildcard");
+        line["target"] = json!(report.target);
+        line["root_domain"] = json!(report.root_domain);
+        serde_json::to_writer(&mut *out, &line)?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+fn write_csv_header(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "kind,target,root_domain,host,sources,first_seen,discovered_at,answers,paths,redirect_chains,wildcard_parent"
+    )
+}
+
+/// Multi-valued columns (paths, sources, answers, redirect chains) are joined
+/// with ';'; the hops of a redirect chain are joined with " -> ".
+/// Evidence and archived URLs are left to the JSON formats.
+fn write_csv(out: &mut dyn Write, report: &Report) -> io::Result<()> {
+    for HostRecord { host, entry } in report.host_records() {
+        let row = [
+            "subdomain".to_string(),
+            report.target.to_string(),
+            report.root_domain.to_string(),
+            host.to_string(),
+            join(&entry.sources),
+            entry.first_seen.clone(),
+            entry.discovered_at.to_string(),
+            join(&entry.answers),
+            join(&entry.paths),
+            join(entry.redirect_chains.iter().map(|chain| chain.join(" -> "))),
+            String::new(),
+        ];
+        write_csv_row(out, &row)?;
+    }
+
+    for m in report.wildcard_matches {
+        let row = [
+            "wildcard".to_string(),
+            report.target.to_string(),
+            report.root_domain.to_string(),
+            m.host.clone(),
+            String::new(),
+            String::new(),
+            String::new(),
+            join(&m.addrs),
+            String::new(),
+            String::new(),
+            m.parent.clone(),
+        ];
+        write_csv_row(out, &row)?;
+    }
+
+    Ok(())
+}
+
+fn join<T: ToString>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn write_csv_row(out: &mut dyn Write, fields: &[String]) -> io::Result<()> {
+    let escaped: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+    writeln!(out, "{}", escaped.join(","))
+}
+
+/// Quote a field if it contains a separator, quote or line break (RFC 4180).
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::*;
+
+    fn sample_map() -> SubdomainMap {
+        let mut map = SubdomainMap::new();
+        let url = Url::parse("https://api.example.com/a,b").unwrap();
+        map.add_url(&url, "example.com", "crtsh");
+        map.add_url(&url, "example.com", "html-crawler");
+        map.record_redirect_chain(&[
+            Url::parse("http://api.example.com/").unwrap(),
+            Url::parse("https://api.example.com/a,b").unwrap(),
+        ]);
+        map
+    }
+
+    fn render(format: OutputFormat) -> String {
+        let map = sample_map();
+        let report = Report {
+            target: "https://example.com",
+            root_domain: "example.com",
+            map: &map,
+            wildcard_matches: &[],
+            sources: &[],
+        };
+        let mut buf = Vec::new();
+        write_reports(&mut buf, format, &[report], false).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_plain_has_no_ansi_codes() {
+        let out = render(OutputFormat::Plain);
+        assert!(out.contains("\napi.example.com\n"));
+        assert!(!out.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_jsonl_records() {
+        let out = render(OutputFormat::Jsonl);
+        let line: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+        assert_eq!(line["host"], "api.example.com");
+        assert_eq!(line["kind"], "subdomain");
+        assert_eq!(line["target"], "https://example.com");
+        assert_eq!(line["sources"], json!(["crtsh", "html-crawler"]));
+    }
+
+    #[test]
+    fn test_csv_escapes_fields() {
+        let out = render(OutputFormat::Csv);
+        let row = out.lines().nth(1).unwrap();
+        assert!(row.starts_with(
+            "subdomain,https://example.com,example.com,api.example.com,crtsh;html-crawler,"
+        ));
+        assert!(
+            row.ends_with(",\"/a,b\",\"http://api.example.com/ -> https://api.example.com/a,b\",")
+        );
+    }
+}
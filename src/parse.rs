@@ -4,29 +4,169 @@ use anyhow::Result;
 use scraper::{Html, Selector};
 use url::Url;
 
-/// Extract all absolute URLs from anchor tags in the given HTML body,
-pub fn extract_links(body: &str, base: &Url) -> Result<Vec<Url>> {
+/// Element/attribute pairs that can reference another URL.
+const URL_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("area", "href"),
+    ("link", "href"),
+    ("script", "src"),
+    ("img", "src"),
+    ("img", "srcset"),
+    ("iframe", "src"),
+    ("frame", "src"),
+    ("embed", "src"),
+    ("form", "action"),
+    ("button", "formaction"),
+    ("input", "formaction"),
+    ("source", "src"),
+    ("source", "srcset"),
+    ("video", "src"),
+    ("video", "poster"),
+    ("audio", "src"),
+    ("track", "src"),
+    ("object", "data"),
+];
+
+/// Elements whose targets are documents worth crawling rather than assets.
+const NAVIGATIONAL: &[&str] = &["a", "area", "iframe", "frame", "form", "meta"];
+
+/// A URL found in a page, together with where in the markup it came from.
+#[derive(Debug, Clone)]
+pub struct ExtractedLink {
+    pub url: Url,
+    pub element: &'static str,
+    pub attribute: &'static str,
+}
+
+impl ExtractedLink {
+    /// Short description of the origin, e.g. "script[src]".
+    pub fn via(&self) -> String {
+        format!("{}[{}]", self.element, self.attribute)
+    }
+
+    /// Whether the link leads to another page (as opposed to a script, image, ...).
+    pub fn is_navigational(&self) -> bool {
+        NAVIGATIONAL.contains(&self.element)
+    }
+
+    /// Whether the link points at JavaScript (a `<script src>` or a `.js`/`.mjs` file).
+    pub fn is_script(&self) -> bool {
+        let path = self.url.path().to_ascii_lowercase();
+        self.element == "script" || path.ends_with(".js") || path.ends_with(".mjs")
+    }
+}
+
+/// Extract all absolute http(s) URLs referenced by the given HTML body:
+/// anchors, scripts, stylesheets, images (including srcset), frames, forms,
+/// media, objects and `<meta http-equiv="refresh">`.
+///
+/// Relative URLs are resolved against `<base href>` if present, else `base`.
+pub fn extract_links(body: &str, base: &Url) -> Result<Vec<ExtractedLink>> {
     let document = Html::parse_document(body);
-    let selector = Selector::parse("a").expect("Failed to parse selector");
+
+    // <base href> changes what relative URLs resolve against
+    let base_selector = Selector::parse("base[href]").expect("Failed to parse selector");
+    let base = document
+        .select(&base_selector)
+        .next()
+        .and_then(|el| el.value().attr("href"))
+        .and_then(|href| base.join(href).ok())
+        .unwrap_or_else(|| base.clone());
 
     let mut out = Vec::new();
+    let mut push = |value: &str, element: &'static str, attribute: &'static str| {
+        if let Some(url) = resolve_link(&base, value) {
+            out.push(ExtractedLink {
+                url,
+                element,
+                attribute,
+            });
+        }
+    };
+
+    for &(element, attribute) in URL_ATTRIBUTES {
+        let selector = Selector::parse(&format!("{}[{}]", element, attribute))
+            .expect("Failed to parse selector");
 
-    for element in document.select(&selector) {
-        if let Some(href) = element.value().attr("href") {
-            // Skip empty / JS pseudo links quickly
-            if href.starts_with('#') || href.starts_with("javascript:") {
+        for el in document.select(&selector) {
+            let Some(value) = el.value().attr(attribute) else {
                 continue;
-            }
+            };
 
-            if let Ok(resolved) = base.join(href) {
-                out.push(resolved);
+            if attribute == "srcset" {
+                for candidate in parse_srcset(value) {
+                    push(candidate, element, attribute);
+                }
+            } else {
+                push(value, element, attribute);
             }
         }
     }
 
+    let meta_selector =
+        Selector::parse("meta[http-equiv][content]").expect("Failed to parse selector");
+    for el in document.select(&meta_selector) {
+        let is_refresh = el
+            .value()
+            .attr("http-equiv")
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("refresh"));
+        if !is_refresh {
+            continue;
+        }
+        if let Some(target) = el.value().attr("content").and_then(parse_meta_refresh) {
+            push(target, "meta", "content");
+        }
+    }
+
     Ok(out)
 }
 
+/// Contents of inline `<script>` blocks (those without a `src`).
+pub fn extract_inline_scripts(body: &str) -> Vec<String> {
+    let document = Html::parse_document(body);
+    let selector = Selector::parse("script:not([src])").expect("Failed to parse selector");
+
+    document
+        .select(&selector)
+        .map(|el| el.text().collect::<String>())
+        .filter(|text| !text.trim().is_empty())
+        .collect()
+}
+
+/// Resolve an attribute value against `base`, keeping only http(s) URLs.
+fn resolve_link(base: &Url, value: &str) -> Option<Url> {
+    let value = value.trim();
+
+    // Skip empty / fragment-only / pseudo links quickly
+    if value.is_empty() || value.starts_with('#') {
+        return None;
+    }
+
+    let resolved = base.join(value).ok()?;
+    matches!(resolved.scheme(), "http" | "https").then_some(resolved)
+}
+
+/// URLs from a srcset value: "a.png 1x, https://cdn.example.com/b.png 2x".
+fn parse_srcset(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .filter_map(|candidate| candidate.split_whitespace().next())
+}
+
+/// Target of a refresh directive such as "5; url='https://example.com/'"
+/// (from `<meta http-equiv="refresh">` or a `Refresh` header).
+pub fn parse_meta_refresh(content: &str) -> Option<&str> {
+    let (_, rest) = content.split_once([';', ','])?;
+    let rest = rest.trim_start();
+    if !rest.get(..3)?.eq_ignore_ascii_case("url") {
+        return None;
+    }
+
+    let target = rest[3..].trim_start().strip_prefix('=')?.trim();
+    Some(target.trim_matches(['\'', '"']))
+}
+
+#[allow(clippy::trim_split_whitespace, clippy::collapsible_str_replace)]
 pub fn extract_csp_links(csp_header: &str) -> Vec<Url> {
     let mut out = Vec::new();
 
@@ -76,3 +216,55 @@ pub fn extract_csp_links(csp_header: &str) -> Vec<Url> {
 
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links_from_resource_attributes() {
+        let body = r#"
+            <html><head>
+              <base href="https://www.example.com/app/">
+              <meta http-equiv="Refresh" content="5; URL='https://login.example.com/'">
+              <link rel="stylesheet" href="//static.example.com/site.css">
+              <script src="https://cdn.example.com/app.js"></script>
+            </head><body>
+              <a href="page">rel</a>
+              <a href="mailto:admin@example.com">mail</a>
+              <img srcset="https://img.example.com/a.png 1x, /b.png 2x">
+              <form action="https://api.example.com/submit"></form>
+              <object data="https://media.example.com/movie.swf"></object>
+            </body></html>
+        "#;
+        let page = Url::parse("https://example.com/").unwrap();
+
+        let links = extract_links(body, &page).unwrap();
+        let found: Vec<(String, String)> =
+            links.iter().map(|l| (l.via(), l.url.to_string())).collect();
+        let has = |via: &str, url: &str| found.contains(&(via.to_string(), url.to_string()));
+
+        assert!(has("a[href]", "https://www.example.com/app/page"));
+        assert!(has("link[href]", "https://static.example.com/site.css"));
+        assert!(has("script[src]", "https://cdn.example.com/app.js"));
+        assert!(has("img[srcset]", "https://img.example.com/a.png"));
+        assert!(has("img[srcset]", "https://www.example.com/b.png"));
+        assert!(has("form[action]", "https://api.example.com/submit"));
+        assert!(has("object[data]", "https://media.example.com/movie.swf"));
+        assert!(has("meta[content]", "https://login.example.com/"));
+        assert!(!found.iter().any(|(_, url)| url.starts_with("mailto:")));
+
+        let script = links.iter().find(|l| l.element == "script").unwrap();
+        assert!(!script.is_navigational());
+        assert!(script.is_script());
+    }
+
+    #[test]
+    fn test_extract_inline_scripts() {
+        let body = r#"<script src="/app.js"></script><script>var api = "https://api.example.com";</script>"#;
+        assert_eq!(
+            extract_inline_scripts(body),
+            vec![r#"var api = "https://api.example.com";"#]
+        );
+    }
+}
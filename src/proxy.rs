@@ -0,0 +1,265 @@
+// src/proxy.rs
+
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+use url::Url;
+
+/// Proxy variables consulted when `--proxy` is not given, in this order.
+/// The first one set is used for every connection, whatever its scheme.
+const PROXY_ENV_VARS: &[&str] = &[
+    "HTTPS_PROXY",
+    "https_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+    "HTTP_PROXY",
+    "http_proxy",
+];
+
+/// Where a source's connections should go.
+///
+/// A proxy from the environment is resolved into `Upstream` up front (see
+/// `from_env`), so raw-socket sources see it exactly like one from `--proxy`.
+#[derive(Clone, Debug, Default)]
+pub enum ProxyMode {
+    /// Connect directly, ignoring any proxy from the environment.
+    #[default]
+    Direct,
+    /// Route through an upstream proxy.
+    Upstream(UpstreamProxy),
+}
+
+/// An upstream HTTP or SOCKS5 proxy plus the hosts that bypass it.
+#[derive(Clone, Debug)]
+pub struct UpstreamProxy {
+    pub url: Url,
+    pub bypass: BypassList,
+}
+
+impl ProxyMode {
+    /// Parse a proxy URL ("http://127.0.0.1:8080", "socks5h://localhost:1080")
+    /// or "direct".
+    pub fn parse(s: &str, bypass: &BypassList) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("direct") || s.eq_ignore_ascii_case("none") {
+            return Ok(ProxyMode::Direct);
+        }
+
+        let mut url = Url::parse(s).with_context(|| format!("invalid proxy URL: {}", s))?;
+        match url.scheme() {
+            "http" | "https" => {}
+            "socks5" | "socks5h" => {
+                if url.port().is_none() {
+                    let _ = url.set_port(Some(1080));
+                }
+            }
+            other => anyhow::bail!("unsupported proxy scheme '{}' in {}", other, s),
+        }
+        if url.host_str().is_none() {
+            anyhow::bail!("proxy URL has no host: {}", s);
+        }
+
+        Ok(ProxyMode::Upstream(UpstreamProxy {
+            url,
+            bypass: bypass.clone(),
+        }))
+    }
+
+    /// The proxy the environment asks for, with `bypass` as its exceptions,
+    /// or `Direct` if no proxy variable is set.
+    pub fn from_env(bypass: &BypassList) -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok(), bypass)
+    }
+
+    /// Like `from_env`, reading variables through `var`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>, bypass: &BypassList) -> Result<Self> {
+        let Some((name, value)) = PROXY_ENV_VARS.iter().find_map(|name| {
+            var(name)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .map(|v| (name, v))
+        }) else {
+            return Ok(ProxyMode::Direct);
+        };
+
+        // Proxy variables often leave out the scheme ("proxy.corp:3128")
+        let value = if value.contains("://") {
+            value
+        } else {
+            format!("http://{}", value)
+        };
+        Self::parse(&value, bypass).with_context(|| format!("invalid proxy in {}", name))
+    }
+
+    /// The proxy a connection to `host` must go through, if any.
+    pub fn upstream_for(&self, host: &str) -> Option<&Url> {
+        match self {
+            ProxyMode::Upstream(p) if !p.bypass.matches(host) => Some(&p.url),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a `--source-proxy` value: `SOURCE=URL` or `SOURCE=direct`.
+pub fn parse_source_proxy(spec: &str, bypass: &BypassList) -> Result<(String, ProxyMode)> {
+    let (name, proxy) = spec
+        .split_once('=')
+        .with_context(|| format!("source proxy must look like 'SOURCE=URL', got: {}", spec))?;
+    Ok((name.trim().to_string(), ProxyMode::parse(proxy, bypass)?))
+}
+
+/// A NO_PROXY-style list of hosts that skip the proxy.
+///
+/// Entries are comma-separated: `*` (everything), a domain (matches itself and
+/// its subdomains; a leading `.` or `*.` is ignored), an IP address, or a CIDR
+/// block such as `10.0.0.0/8`.
+#[derive(Clone, Debug, Default)]
+pub struct BypassList {
+    rules: Vec<BypassRule>,
+}
+
+#[derive(Clone, Debug)]
+enum BypassRule {
+    All,
+    Domain(String),
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+}
+
+impl BypassList {
+    pub fn parse(list: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+
+        for entry in list.split(',') {
+            let entry = entry.trim().to_lowercase();
+            if entry.is_empty() {
+                continue;
+            }
+
+            let rule = if entry == "*" {
+                BypassRule::All
+            } else if let Some((ip, bits)) = entry.split_once('/') {
+                let ip: IpAddr = ip
+                    .parse()
+                    .with_context(|| format!("invalid CIDR in no-proxy list: {}", entry))?;
+                let bits: u8 = bits
+                    .parse()
+                    .with_context(|| format!("invalid CIDR in no-proxy list: {}", entry))?;
+                let max = if ip.is_ipv4() { 32 } else { 128 };
+                if bits > max {
+                    anyhow::bail!("invalid CIDR prefix in no-proxy list: {}", entry);
+                }
+                BypassRule::Cidr(ip, bits)
+            } else if let Ok(ip) = entry.trim_matches(['[', ']']).parse::<IpAddr>() {
+                BypassRule::Ip(ip)
+            } else {
+                let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+                BypassRule::Domain(domain.to_string())
+            };
+            rules.push(rule);
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// Read NO_PROXY / no_proxy from the environment (empty if unset).
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("NO_PROXY").or_else(|_| std::env::var("no_proxy")) {
+            Ok(list) => Self::parse(&list),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.trim_matches(['[', ']']).to_lowercase();
+        let ip = host.parse::<IpAddr>().ok();
+
+        self.rules.iter().any(|rule| match rule {
+            BypassRule::All => true,
+            BypassRule::Domain(d) => host == *d || host.ends_with(&format!(".{}", d)),
+            BypassRule::Ip(rule_ip) => ip == Some(*rule_ip),
+            BypassRule::Cidr(net, bits) => ip.is_some_and(|ip| in_cidr(ip, *net, *bits)),
+        })
+    }
+}
+
+fn in_cidr(ip: IpAddr, net: IpAddr, bits: u8) -> bool {
+    match (ip, net) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(bits)).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(bits)).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bypass_list_matching() {
+        let list = BypassList::parse("localhost, .internal.example.com,10.0.0.0/8,::1").unwrap();
+
+        assert!(list.matches("localhost"));
+        assert!(list.matches("git.internal.example.com"));
+        assert!(list.matches("internal.example.com"));
+        assert!(list.matches("10.20.30.40"));
+        assert!(list.matches("[::1]"));
+
+        assert!(!list.matches("www.example.com"));
+        assert!(!list.matches("11.0.0.1"));
+        assert!(!list.matches("notlocalhost"));
+    }
+
+    #[test]
+    fn test_proxy_mode_parse() {
+        let bypass = BypassList::parse("127.0.0.1").unwrap();
+
+        let mode = ProxyMode::parse("socks5h://localhost", &bypass).unwrap();
+        assert_eq!(
+            mode.upstream_for("example.com").map(Url::as_str),
+            Some("socks5h://localhost:1080")
+        );
+        assert!(mode.upstream_for("127.0.0.1").is_none());
+
+        assert!(matches!(
+            ProxyMode::parse("direct", &bypass).unwrap(),
+            ProxyMode::Direct
+        ));
+        assert!(ProxyMode::parse("ftp://proxy", &bypass).is_err());
+    }
+
+    #[test]
+    fn test_proxy_mode_from_env_vars() {
+        let bypass = BypassList::parse("localhost").unwrap();
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+
+        let mode = ProxyMode::from_vars(
+            env(&[
+                ("http_proxy", "http://a:1"),
+                ("ALL_PROXY", "proxy.corp:3128"),
+            ]),
+            &bypass,
+        )
+        .unwrap();
+        assert_eq!(
+            mode.upstream_for("example.com").map(Url::as_str),
+            Some("http://proxy.corp:3128/")
+        );
+        assert!(mode.upstream_for("localhost").is_none());
+
+        let mode = ProxyMode::from_vars(env(&[("HTTPS_PROXY", " ")]), &bypass).unwrap();
+        assert!(matches!(mode, ProxyMode::Direct));
+    }
+}
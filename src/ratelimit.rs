@@ -0,0 +1,179 @@
+// src/ratelimit.rs
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::sources::SourceContext;
+
+/// First delay after a host throttles us without saying for how long.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Upper bound for both our own backoff and a server's Retry-After.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Longest single sleep while waiting, so cancellation is noticed promptly.
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+
+/// Throttling knobs for requests sent to the target.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RateLimitSettings {
+    /// Global cap across all hosts; `None` means unlimited.
+    pub requests_per_second: Option<f64>,
+
+    /// Minimum gap between two requests to the same host.
+    pub per_host_delay: Duration,
+}
+
+/// A global token bucket combined with per-host politeness delays.
+///
+/// Hosts that answer 429/503 are pushed back by their Retry-After, or by an
+/// exponential backoff when they don't send one.
+#[derive(Debug)]
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    /// Tokens currently in the global bucket
+    tokens: f64,
+    last_refill: Instant,
+
+    /// Earliest time the next request to each host may start
+    next_allowed: HashMap<String, Instant>,
+
+    /// Current backoff for hosts that are throttling us
+    backoff: HashMap<String, Duration>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            settings,
+            state: Mutex::new(LimiterState {
+                tokens: bucket_size(settings.requests_per_second),
+                last_refill: Instant::now(),
+                next_allowed: HashMap::new(),
+                backoff: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Block until a request to `host` may be sent.
+    /// Returns false if `ctx` was cancelled while waiting.
+    pub fn acquire(&self, host: &str, ctx: &SourceContext) -> bool {
+        loop {
+            if ctx.is_cancelled() {
+                return false;
+            }
+
+            match self.try_acquire(host, Instant::now()) {
+                Ok(()) => return true,
+                Err(wait) => thread::sleep(wait.min(WAIT_SLICE)),
+            }
+        }
+    }
+
+    /// Take a slot for `host` if one is free at `now`, otherwise say how long to wait.
+    fn try_acquire(&self, host: &str, now: Instant) -> Result<(), Duration> {
+        let mut st = self.state.lock().unwrap();
+
+        if let Some(next) = st.next_allowed.get(host)
+            && *next > now
+        {
+            return Err(*next - now);
+        }
+
+        if let Some(rps) = self.settings.requests_per_second {
+            let elapsed = now.saturating_duration_since(st.last_refill).as_secs_f64();
+            st.tokens = (st.tokens + elapsed * rps).min(bucket_size(Some(rps)));
+            st.last_refill = now;
+
+            if st.tokens < 1.0 {
+                return Err(Duration::from_secs_f64((1.0 - st.tokens) / rps));
+            }
+            st.tokens -= 1.0;
+        }
+
+        st.next_allowed
+            .insert(host.to_string(), now + self.settings.per_host_delay);
+        Ok(())
+    }
+
+    /// Back off from a host that answered 429/503.
+    /// Returns how long the host is now paused for.
+    pub fn throttled(&self, host: &str, retry_after: Option<Duration>) -> Duration {
+        let mut st = self.state.lock().unwrap();
+
+        let backoff = match st.backoff.get(host) {
+            Some(prev) => (*prev * 2).min(MAX_BACKOFF),
+            None => MIN_BACKOFF,
+        };
+        st.backoff.insert(host.to_string(), backoff);
+
+        let delay = retry_after.map_or(backoff, |ra| ra.min(MAX_BACKOFF));
+        let until = Instant::now() + delay;
+        let next = st.next_allowed.entry(host.to_string()).or_insert(until);
+        *next = (*next).max(until);
+
+        delay
+    }
+
+    /// Forget any backoff once a host answers normally again.
+    pub fn succeeded(&self, host: &str) {
+        self.state.lock().unwrap().backoff.remove(host);
+    }
+}
+
+/// Burst size of the global bucket: one second's worth of requests, at least one.
+fn bucket_size(requests_per_second: Option<f64>) -> f64 {
+    requests_per_second.map_or(0.0, |rps| rps.max(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_host_delay_and_global_cap() {
+        let limiter = RateLimiter::new(RateLimitSettings {
+            requests_per_second: Some(2.0),
+            per_host_delay: Duration::from_secs(1),
+        });
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire("a.example.com", now).is_ok());
+        // Same host must wait out the politeness delay
+        assert!(limiter.try_acquire("a.example.com", now).is_err());
+        // Another host can go, until the bucket is empty
+        assert!(limiter.try_acquire("b.example.com", now).is_ok());
+        assert!(limiter.try_acquire("c.example.com", now).is_err());
+        assert!(
+            limiter
+                .try_acquire("c.example.com", now + Duration::from_millis(500))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_throttling_backs_off() {
+        let limiter = RateLimiter::new(RateLimitSettings::default());
+
+        assert_eq!(limiter.throttled("a.example.com", None), MIN_BACKOFF);
+        assert_eq!(limiter.throttled("a.example.com", None), MIN_BACKOFF * 2);
+        assert!(
+            limiter
+                .try_acquire("a.example.com", Instant::now())
+                .is_err()
+        );
+
+        let ra = Duration::from_secs(30);
+        assert_eq!(limiter.throttled("b.example.com", Some(ra)), ra);
+
+        limiter.succeeded("a.example.com");
+        assert_eq!(limiter.throttled("a.example.com", None), MIN_BACKOFF);
+    }
+}
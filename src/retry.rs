@@ -0,0 +1,202 @@
+// src/retry.rs
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use colored::Colorize;
+use rand::Rng;
+use reqwest::StatusCode;
+
+use crate::http::HttpStatusError;
+use crate::sources::SourceContext;
+
+/// Longest single sleep while backing off, so cancellation is noticed promptly.
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+
+/// When and how often to retry a failed request.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Total tries, including the first one.
+    pub max_attempts: u32,
+
+    /// Delay before the first retry; doubles with every further attempt.
+    pub base_delay: Duration,
+
+    /// Cap for both the computed backoff and a server's Retry-After.
+    pub max_delay: Duration,
+
+    /// Also retry 429/503. Callers that throttle on their own (the crawler's
+    /// rate limiter) turn this off and handle those responses themselves.
+    pub retry_throttled: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retry_throttled: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Run `op` until it succeeds, fails with a non-retryable error, runs out
+    /// of attempts, or `ctx` is cancelled. Every attempt is counted on `ctx`.
+    pub fn run<T>(
+        &self,
+        ctx: &SourceContext,
+        what: &str,
+        mut op: impl FnMut() -> Result<T>,
+    ) -> Result<T> {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+
+        loop {
+            ctx.record_attempt(attempt > 1);
+
+            let err = match op() {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+
+            if attempt >= max_attempts || !self.is_retryable(&err) || ctx.is_cancelled() {
+                return Err(err);
+            }
+
+            let delay = retry_after(&err)
+                .map(|ra| ra.min(self.max_delay))
+                .unwrap_or_else(|| self.backoff(attempt));
+            eprintln!(
+                "{} {} failed (attempt {}/{}): {:#}; retrying in {:.1}s",
+                "[~]".yellow().bold(),
+                what,
+                attempt,
+                max_attempts,
+                err,
+                delay.as_secs_f64()
+            );
+
+            if !sleep_unless_cancelled(delay, ctx) {
+                return Err(err);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Exponential backoff with jitter: a random delay in the upper half of
+    /// `base_delay * 2^(attempt - 1)`, capped at `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_delay);
+        let jitter = rand::thread_rng().gen_range(0.5..=1.0);
+        exp.mul_f64(jitter)
+    }
+
+    /// Timeouts, connection failures, truncated bodies and 408/425/429/5xx
+    /// gateway-style statuses are worth another try; anything else is not.
+    pub fn is_retryable(&self, err: &anyhow::Error) -> bool {
+        err.chain().any(|cause| {
+            if let Some(status) = cause.downcast_ref::<HttpStatusError>() {
+                return match status.status {
+                    StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                        self.retry_throttled
+                    }
+                    StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::TOO_EARLY
+                    | StatusCode::INTERNAL_SERVER_ERROR
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::GATEWAY_TIMEOUT => true,
+                    _ => false,
+                };
+            }
+
+            cause
+                .downcast_ref::<reqwest::Error>()
+                .is_some_and(|e| e.is_timeout() || e.is_connect() || e.is_request() || e.is_body())
+        })
+    }
+}
+
+fn retry_after(err: &anyhow::Error) -> Option<Duration> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<HttpStatusError>())
+        .and_then(|status| status.retry_after)
+}
+
+/// Returns false if `ctx` was cancelled before `delay` passed.
+fn sleep_unless_cancelled(delay: Duration, ctx: &SourceContext) -> bool {
+    let until = Instant::now() + delay;
+    loop {
+        if ctx.is_cancelled() {
+            return false;
+        }
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        thread::sleep(left.min(WAIT_SLICE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        }
+    }
+
+    fn status(code: u16) -> anyhow::Error {
+        HttpStatusError {
+            status: StatusCode::from_u16(code).unwrap(),
+            retry_after: None,
+            host: "api.example.com".to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_retries_transient_statuses() {
+        let ctx = SourceContext::new();
+        let mut calls = 0;
+
+        let result = policy().run(&ctx, "query", || {
+            calls += 1;
+            if calls < 3 {
+                Err(status(502))
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(ctx.attempts(), 3);
+        assert_eq!(ctx.retries(), 2);
+    }
+
+    #[test]
+    fn test_gives_up_on_permanent_errors() {
+        let ctx = SourceContext::new();
+
+        let result: Result<()> = policy().run(&ctx, "query", || {
+            Err(status(404).context("crt.sh query failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(ctx.attempts(), 1);
+
+        let no_throttle = RetryPolicy {
+            retry_throttled: false,
+            ..policy()
+        };
+        assert!(!no_throttle.is_retryable(&status(429)));
+        assert!(policy().is_retryable(&status(429)));
+    }
+}
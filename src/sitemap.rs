@@ -0,0 +1,216 @@
+// src/sitemap.rs
+
+use std::io::Read;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use url::Url;
+
+/// Upper bound for a sitemap, compressed or not (the protocol allows 50 MB).
+pub const MAX_SITEMAP_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Upper bound for robots.txt (crawlers commonly stop reading at 500 KiB).
+pub const MAX_ROBOTS_BYTES: u64 = 500 * 1024;
+
+/// What robots.txt tells us about a host.
+#[derive(Debug, Default)]
+pub struct Robots {
+    /// `Sitemap:` directives
+    pub sitemaps: Vec<Url>,
+
+    /// Paths named in `Allow:` / `Disallow:` rules (wildcards cut off)
+    pub paths: Vec<Url>,
+}
+
+/// A parsed sitemap: either an index of further sitemaps or a list of pages.
+#[derive(Debug, PartialEq)]
+pub enum Sitemap {
+    Index(Vec<Url>),
+    UrlSet(Vec<Url>),
+}
+
+/// Parse robots.txt, resolving everything against `robots_url`.
+pub fn parse_robots(body: &str, robots_url: &Url) -> Robots {
+    let mut robots = Robots::default();
+
+    for line in body.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+
+        match key.trim().to_ascii_lowercase().as_str() {
+            "sitemap" => {
+                if let Ok(url) = robots_url.join(value) {
+                    robots.sitemaps.push(url);
+                }
+            }
+            "allow" | "disallow" => {
+                // "/private/*.json$" -> "/private/"
+                let path = value.split(['*', '$']).next().unwrap_or_default();
+                if !path.starts_with('/') || path == "/" {
+                    continue;
+                }
+                if let Ok(url) = robots_url.join(path)
+                    && !robots.paths.contains(&url)
+                {
+                    robots.paths.push(url);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    robots
+}
+
+/// Parse an XML sitemap or sitemap index, gzip-compressed or not.
+/// Plain-text sitemaps (one URL per line) are accepted as well; there only
+/// absolute http(s) URLs count, so an error page served as text yields nothing.
+pub fn parse_sitemap(body: &[u8], sitemap_url: &Url) -> Result<Sitemap> {
+    let data = if body.starts_with(&[0x1f, 0x8b]) {
+        let mut out = Vec::new();
+        GzDecoder::new(body)
+            .take(MAX_SITEMAP_BYTES)
+            .read_to_end(&mut out)
+            .context("failed to decompress gzip sitemap")?;
+        out
+    } else {
+        body[..body.len().min(MAX_SITEMAP_BYTES as usize)].to_vec()
+    };
+    let text = String::from_utf8_lossy(&data);
+
+    let resolve = |loc: &str| sitemap_url.join(loc.trim()).ok();
+
+    if !text.trim_start().starts_with('<') {
+        let urls = text
+            .lines()
+            .filter_map(|line| Url::parse(line.trim()).ok())
+            .filter(|url| matches!(url.scheme(), "http" | "https"));
+        return Ok(Sitemap::UrlSet(urls.collect()));
+    }
+
+    let locs: Vec<Url> = extract_locs(&text)
+        .iter()
+        .filter_map(|loc| resolve(loc))
+        .collect();
+
+    if text.contains("<sitemapindex") {
+        Ok(Sitemap::Index(locs))
+    } else {
+        Ok(Sitemap::UrlSet(locs))
+    }
+}
+
+/// Contents of every `<loc>` element, unescaped.
+fn extract_locs(xml: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find("<loc>") {
+        rest = &rest[start + "<loc>".len()..];
+        let Some(end) = rest.find("</loc>") else {
+            break;
+        };
+
+        let loc = rest[..end].trim();
+        let loc = loc
+            .strip_prefix("<![CDATA[")
+            .and_then(|l| l.strip_suffix("]]>"))
+            .unwrap_or(loc);
+        out.push(unescape_xml(loc));
+        rest = &rest[end..];
+    }
+
+    out
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_robots() {
+        let robots_url = Url::parse("https://www.example.com/robots.txt").unwrap();
+        let body = "User-agent: *\n\
+                    Disallow: /admin/ # staff only\n\
+                    Disallow: /api/*.json$\n\
+                    Allow: /\n\
+                    Sitemap: https://static.example.com/sitemap_index.xml\n";
+
+        let robots = parse_robots(body, &robots_url);
+        assert_eq!(
+            robots.sitemaps,
+            vec![Url::parse("https://static.example.com/sitemap_index.xml").unwrap()]
+        );
+        let paths: Vec<&str> = robots.paths.iter().map(Url::as_str).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "https://www.example.com/admin/",
+                "https://www.example.com/api/"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sitemap_index_and_gzip() {
+        let base = Url::parse("https://www.example.com/sitemap.xml").unwrap();
+
+        let index = r#"<?xml version="1.0"?>
+            <sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+              <sitemap><loc>https://www.example.com/pages.xml.gz</loc></sitemap>
+            </sitemapindex>"#;
+        assert_eq!(
+            parse_sitemap(index.as_bytes(), &base).unwrap(),
+            Sitemap::Index(vec![
+                Url::parse("https://www.example.com/pages.xml.gz").unwrap()
+            ])
+        );
+
+        let urlset = r#"<urlset><url><loc> https://shop.example.com/?a=1&amp;b=2 </loc></url>
+            <url><loc><![CDATA[https://blog.example.com/post]]></loc></url></urlset>"#;
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(urlset.as_bytes()).unwrap();
+        let compressed = gz.finish().unwrap();
+
+        assert_eq!(
+            parse_sitemap(&compressed, &base).unwrap(),
+            Sitemap::UrlSet(vec![
+                Url::parse("https://shop.example.com/?a=1&b=2").unwrap(),
+                Url::parse("https://blog.example.com/post").unwrap(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_text_sitemap() {
+        let base = Url::parse("https://www.example.com/sitemap.txt").unwrap();
+
+        let body = "https://www.example.com/about\n\nmailto:a@example.com\n/relative\n";
+        assert_eq!(
+            parse_sitemap(body.as_bytes(), &base).unwrap(),
+            Sitemap::UrlSet(vec![Url::parse("https://www.example.com/about").unwrap()])
+        );
+
+        // A soft 404 served as text is not a list of pages
+        assert_eq!(
+            parse_sitemap(b"Not Found", &base).unwrap(),
+            Sitemap::UrlSet(vec![])
+        );
+    }
+}
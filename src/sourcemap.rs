@@ -0,0 +1,182 @@
+// src/sourcemap.rs
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use url::Url;
+
+use crate::js;
+
+/// Where a script says its source map lives.
+#[derive(Debug, PartialEq)]
+pub enum SourceMapRef {
+    /// A separate file to fetch
+    Url(Url),
+    /// Embedded as a `data:` URI, already decoded
+    Inline(String),
+}
+
+/// The parts of a source map (revision 3) we care about.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct SourceMap {
+    sources: Vec<Option<String>>,
+    sources_content: Vec<Option<String>>,
+
+    /// Index maps nest complete maps in sections
+    sections: Vec<Section>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Section {
+    map: Option<SourceMap>,
+}
+
+/// Find the `//# sourceMappingURL=` comment in a script (the last one wins,
+/// as in browsers) and resolve it against the script's URL.
+pub fn find_source_map_ref(script: &str, script_url: &Url) -> Option<SourceMapRef> {
+    let value = script.lines().rev().find_map(|line| {
+        let line = line.trim();
+        let rest = line
+            .strip_prefix("//# sourceMappingURL=")
+            .or_else(|| line.strip_prefix("//@ sourceMappingURL="))
+            .or_else(|| line.strip_prefix("/*# sourceMappingURL="))?;
+        rest.split_whitespace().next()
+    })?;
+    let value = value.trim_end_matches("*/");
+
+    if let Some(data) = value.strip_prefix("data:") {
+        return decode_data_uri(data).map(SourceMapRef::Inline);
+    }
+
+    script_url.join(value).ok().map(SourceMapRef::Url)
+}
+
+/// Decode "application/json;base64,eyJ2..." (the part after `data:`).
+fn decode_data_uri(data: &str) -> Option<String> {
+    let (meta, payload) = data.split_once(',')?;
+
+    let bytes = if meta.ends_with(";base64") {
+        STANDARD.decode(payload).ok()?
+    } else {
+        percent_decode(payload)
+    };
+    String::from_utf8(bytes).ok()
+}
+
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(b) => {
+                out.push(b);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Parse a source map and find in-scope URLs and hostnames mentioned in the
+/// embedded original sources (`sourcesContent`) and their file names.
+pub fn find_references(map_json: &str, root_domain: &str) -> Result<Vec<Url>> {
+    let map: SourceMap = serde_json::from_str(map_json).context("invalid source map")?;
+
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    collect_references(&map, root_domain, &mut out, &mut seen);
+    Ok(out)
+}
+
+fn collect_references(
+    map: &SourceMap,
+    root_domain: &str,
+    out: &mut Vec<Url>,
+    seen: &mut HashSet<String>,
+) {
+    let texts = map.sources.iter().chain(&map.sources_content).flatten();
+    for text in texts {
+        for url in js::find_references(text, root_domain) {
+            if seen.insert(url.to_string()) {
+                out.push(url);
+            }
+        }
+    }
+
+    for section in &map.sections {
+        if let Some(map) = &section.map {
+            collect_references(map, root_domain, out, seen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_source_map_ref() {
+        let script_url = Url::parse("https://cdn.example.com/js/app.min.js").unwrap();
+
+        let js = "console.log(1);\n//# sourceMappingURL=app.min.js.map\n";
+        assert_eq!(
+            find_source_map_ref(js, &script_url),
+            Some(SourceMapRef::Url(
+                Url::parse("https://cdn.example.com/js/app.min.js.map").unwrap()
+            ))
+        );
+
+        let js = "x();\n//# sourceMappingURL=data:application/json;charset=utf-8;base64,eyJ2ZXJzaW9uIjozfQ==";
+        assert_eq!(
+            find_source_map_ref(js, &script_url),
+            Some(SourceMapRef::Inline(r#"{"version":3}"#.to_string()))
+        );
+
+        assert_eq!(find_source_map_ref("x();", &script_url), None);
+    }
+
+    #[test]
+    fn test_find_references_in_sources_content() {
+        let map = r#"{
+            "version": 3,
+            "sources": ["webpack:///src/config.js", null],
+            "sourcesContent": [
+                "export const API = 'https://internal-api.example.com/v1';\nconst DB = 'db.corp.example.com';",
+                null
+            ],
+            "sections": [{"offset": {"line": 0, "column": 0}, "map": {
+                "sources": [], "sourcesContent": ["'//staging.example.com'"]
+            }}]
+        }"#;
+
+        let found: Vec<String> = find_references(map, "example.com")
+            .unwrap()
+            .iter()
+            .map(Url::to_string)
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "https://internal-api.example.com/v1",
+                "https://db.corp.example.com/",
+                "https://staging.example.com/",
+            ]
+        );
+        assert!(find_references("not json", "example.com").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// src/sources/api.rs
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::HeaderMap;
+use url::Url;
+
+use crate::http::HttpStatusError;
+use crate::sources::{DiscoveryConfig, Quota, SourceContext};
+use crate::subdomains::{SubdomainMap, host_in_scope};
+
+/// Where a third-party API lives and how to authenticate with it.
+#[derive(Clone, Debug)]
+pub struct ApiEndpoint {
+    /// Base URL, always ending in '/'
+    pub base_url: Url,
+    pub api_key: Option<String>,
+}
+
+impl ApiEndpoint {
+    /// Use `base_url` if given (e.g. a local mock), otherwise `default`.
+    pub fn new(default: &str, base_url: Option<&str>, api_key: Option<String>) -> Result<Self> {
+        let raw = base_url.unwrap_or(default);
+        let mut base_url =
+            Url::parse(raw.trim()).with_context(|| format!("invalid base URL: {}", raw))?;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        Ok(Self { base_url, api_key })
+    }
+
+    /// `path` (relative, without a leading '/') resolved against the base URL.
+    pub fn url(&self, path: &str) -> Result<Url> {
+        self.base_url
+            .join(path)
+            .with_context(|| format!("invalid API path: {}", path))
+    }
+}
+
+/// Send the request `build` creates through the source's retry policy and
+/// decode the response body with `parse`.
+///
+/// Non-success statuses fail with an `HttpStatusError`; quota headers are
+/// recorded on `ctx` whatever the status. Errors never include the request
+/// URL, which may carry an API key.
+pub fn get<T>(
+    cfg: &DiscoveryConfig,
+    ctx: &SourceContext,
+    what: &str,
+    build: impl Fn() -> RequestBuilder,
+    parse: impl Fn(&[u8]) -> Result<T>,
+) -> Result<T> {
+    cfg.retry.run(ctx, what, || {
+        ctx.record_api_request();
+        let resp = build()
+            .send()
+            .map_err(reqwest::Error::without_url)
+            .with_context(|| format!("failed to send {} request", what))?;
+
+        if let Some(quota) = quota_from_headers(resp.headers()) {
+            ctx.record_quota(quota);
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow::Error::from(HttpStatusError::from_response(&resp))
+                .context(format!("{} returned non-success status code", what)));
+        }
+
+        let body = resp
+            .bytes()
+            .map_err(reqwest::Error::without_url)
+            .with_context(|| format!("failed to read {} response body", what))?;
+        parse(&body)
+    })
+}
+
+/// Read the remaining request quota from the headers common APIs use:
+/// `X-RateLimit-Remaining`/`-Limit` (and the `X-Rate-Limit-` spelling), or
+/// HackerTarget's `X-API-Count` (used) and `X-API-Quota` (allowed).
+fn quota_from_headers(headers: &HeaderMap) -> Option<Quota> {
+    let number = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            headers
+                .get(*name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        })
+    };
+
+    let limit = number(&["x-ratelimit-limit", "x-rate-limit-limit"]);
+    if let Some(remaining) = number(&["x-ratelimit-remaining", "x-rate-limit-remaining"]) {
+        return Some(Quota { remaining, limit });
+    }
+
+    let used = number(&["x-api-count"])?;
+    let limit = number(&["x-api-quota"])?;
+    Some(Quota {
+        remaining: limit.saturating_sub(used),
+        limit: Some(limit),
+    })
+}
+
+/// Add a hostname an API reported if it is inside the root domain.
+/// Returns whether the host is new to `map`.
+pub fn record_host(
+    map: &mut SubdomainMap,
+    name: &str,
+    cfg: &DiscoveryConfig,
+    source: &str,
+) -> bool {
+    let host = name.trim().trim_end_matches('.').to_lowercase();
+    if host.is_empty() || host.contains('*') || !host_in_scope(&host, &cfg.root_domain) {
+        return false;
+    }
+    let Ok(url) = Url::parse(&format!("https://{}/", host)) else {
+        return false;
+    };
+
+    let is_new = map.add_url(&url, &cfg.root_domain, source);
+    if is_new {
+        eprintln!(
+            "{} Discovered potential (sub)domain {} via {}",
+            "[+]".green().bold(),
+            host.bold(),
+            source
+        );
+    }
+    is_new
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_quota_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(quota_from_headers(&headers), None);
+
+        headers.insert("x-api-count", HeaderValue::from_static("12"));
+        headers.insert("x-api-quota", HeaderValue::from_static("50"));
+        assert_eq!(
+            quota_from_headers(&headers),
+            Some(Quota {
+                remaining: 38,
+                limit: Some(50)
+            })
+        );
+
+        headers.insert("x-rate-limit-remaining", HeaderValue::from_static("99"));
+        assert_eq!(
+            quota_from_headers(&headers),
+            Some(Quota {
+                remaining: 99,
+                limit: None
+            })
+        );
+    }
+}
@@ -0,0 +1,178 @@
+// src/sources/certspotter.rs
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::sources::api::{self, ApiEndpoint};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.certspotter.com/";
+
+/// Pages fetched at most, each holding around a hundred issuances; a warning
+/// says so when results are cut off there.
+const MAX_PAGES: usize = 100;
+
+/// Certificate issuances from SSLMate's CertSpotter API.
+pub struct CertSpotter {
+    endpoint: ApiEndpoint,
+}
+
+impl CertSpotter {
+    pub fn new(endpoint: ApiEndpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+/// One item of `/v1/issuances?expand=dns_names`.
+#[derive(Deserialize, Debug)]
+struct Issuance {
+    id: String,
+    #[serde(default)]
+    dns_names: Vec<String>,
+}
+
+impl SubdomainSource for CertSpotter {
+    fn name(&self) -> &'static str {
+        "certspotter"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        eprintln!("[*] Querying CertSpotter for domain {}", cfg.root_domain);
+
+        let mut map = SubdomainMap::new();
+        // Pages continue after the id of the last issuance seen
+        let mut after: Option<String> = None;
+
+        for page_no in 1..=MAX_PAGES {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            let mut url = self.endpoint.url("v1/issuances")?;
+            url.query_pairs_mut()
+                .append_pair("domain", &cfg.root_domain)
+                .append_pair("include_subdomains", "true")
+                .append_pair("expand", "dns_names");
+            if let Some(after) = &after {
+                url.query_pairs_mut().append_pair("after", after);
+            }
+
+            let page: Vec<Issuance> = api::get(
+                cfg,
+                ctx,
+                "CertSpotter query",
+                || {
+                    let req = cfg.http.get(url.as_str());
+                    match &self.endpoint.api_key {
+                        Some(key) => req.bearer_auth(key),
+                        None => req,
+                    }
+                },
+                |body| {
+                    serde_json::from_slice(body)
+                        .context("Failed to parse JSON response from CertSpotter")
+                },
+            )?;
+
+            let Some(last) = page.last() else {
+                break;
+            };
+            after = Some(last.id.clone());
+
+            for name in page.iter().flat_map(|i| &i.dns_names) {
+                api::record_host(&mut map, name, cfg, self.name());
+            }
+
+            if page_no == MAX_PAGES {
+                ctx.warn(format!(
+                    "CertSpotter results cut off after {} pages (last issuance {})",
+                    MAX_PAGES, last.id
+                ));
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::http::stub_server::{self, response};
+
+    #[test]
+    fn test_paginates_with_after_and_sends_key() {
+        let port = stub_server::spawn(|req, _| {
+            if req.header("authorization") != Some("Bearer secret") {
+                return response("401 Unauthorized", &[], "");
+            }
+            let body = if req.path.contains("after=2") {
+                json!([])
+            } else if req.path.contains("after=1") {
+                json!([{"id": "2", "dns_names": ["*.cdn.example.com", "api.example.com"]}])
+            } else {
+                json!([{"id": "1", "dns_names": ["example.com", "www.example.com", "other.org"]}])
+            };
+            response("200 OK", &[], &body.to_string())
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            Some("secret".to_string()),
+        )
+        .unwrap();
+
+        let ctx = SourceContext::new();
+        let map = CertSpotter::new(endpoint).discover(&cfg, &ctx).unwrap();
+        assert_eq!(
+            map.hosts(),
+            vec!["api.example.com", "example.com", "www.example.com"]
+        );
+        assert_eq!(ctx.attempts(), 3);
+        assert_eq!(ctx.api_requests(), 3);
+        assert_eq!(ctx.quota(), None);
+    }
+
+    #[test]
+    fn test_warns_when_page_cap_cuts_results_off() {
+        // Every page has another issuance after it
+        let port = stub_server::spawn(|req, _| {
+            let after = req
+                .path
+                .split("after=")
+                .nth(1)
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(0);
+            let body = json!([{"id": (after + 1).to_string(), "dns_names": ["www.example.com"]}]);
+            response("200 OK", &[], &body.to_string())
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            None,
+        )
+        .unwrap();
+
+        let ctx = SourceContext::new();
+        CertSpotter::new(endpoint).discover(&cfg, &ctx).unwrap();
+        assert_eq!(ctx.api_requests(), MAX_PAGES);
+        assert_eq!(
+            ctx.take_warnings(),
+            vec![format!(
+                "CertSpotter results cut off after {} pages (last issuance {})",
+                MAX_PAGES, MAX_PAGES
+            )]
+        );
+    }
+}
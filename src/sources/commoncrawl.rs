@@ -0,0 +1,299 @@
+// src/sources/commoncrawl.rs
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use url::Url;
+
+use crate::http::HttpStatusError;
+use crate::sources::api::ApiEndpoint;
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+pub const DEFAULT_BASE_URL: &str = "https://index.commoncrawl.org/";
+
+/// Crawl indexes queried unless configured otherwise, newest first.
+pub const DEFAULT_MAX_INDEXES: usize = 3;
+
+/// Result pages fetched at most per index; a warning says so when an index
+/// has more.
+const MAX_PAGES_PER_INDEX: u64 = 50;
+
+/// Index queries for large domains take a while, so a shorter `--timeout`
+/// is raised to this for them.
+const MIN_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// URLs captured by Common Crawl, read from the CDX index of each crawl.
+pub struct CommonCrawl {
+    endpoint: ApiEndpoint,
+    max_indexes: usize,
+}
+
+impl CommonCrawl {
+    pub fn new(endpoint: ApiEndpoint, max_indexes: usize) -> Self {
+        Self {
+            endpoint,
+            max_indexes,
+        }
+    }
+}
+
+/// One crawl listed in `collinfo.json`.
+#[derive(Deserialize, Debug)]
+struct Collection {
+    id: String,
+
+    /// CDX endpoint of the crawl's index
+    #[serde(rename = "cdx-api")]
+    cdx_api: String,
+}
+
+/// Answer to a `showNumPages=true` query.
+#[derive(Deserialize, Debug)]
+struct PageCount {
+    pages: u64,
+}
+
+/// One line of a CDX result page (`output=json` is one object per line).
+#[derive(Deserialize, Debug)]
+struct Capture {
+    url: String,
+}
+
+impl SubdomainSource for CommonCrawl {
+    fn name(&self) -> &'static str {
+        "commoncrawl"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        let collinfo = self.endpoint.url("collinfo.json")?;
+        let text = fetch_text(cfg, ctx, "Common Crawl collection list", &collinfo)?
+            .context("Common Crawl collection list not found")?;
+        let collections: Vec<Collection> =
+            serde_json::from_str(&text).context("Failed to parse Common Crawl collinfo.json")?;
+
+        // collinfo.json lists the newest crawl first
+        let selected: Vec<&Collection> = collections.iter().take(self.max_indexes).collect();
+        eprintln!(
+            "[*] Querying {} of {} Common Crawl indexes for domain {}",
+            selected.len(),
+            collections.len(),
+            cfg.root_domain
+        );
+
+        let mut map = SubdomainMap::new();
+        let mut errors = Vec::new();
+        for collection in &selected {
+            if ctx.is_cancelled() {
+                break;
+            }
+            if let Err(e) = self.query_index(collection, cfg, ctx, &mut map) {
+                errors.push(e.context(format!("Common Crawl index {}", collection.id)));
+            }
+        }
+
+        if !errors.is_empty() && errors.len() == selected.len() {
+            let first = errors.into_iter().next().unwrap();
+            return Err(first.context("every Common Crawl index failed"));
+        }
+        for e in errors {
+            ctx.warn(format!("{:#}", e));
+        }
+
+        Ok(map)
+    }
+}
+
+impl CommonCrawl {
+    fn query_index(
+        &self,
+        collection: &Collection,
+        cfg: &DiscoveryConfig,
+        ctx: &SourceContext,
+        map: &mut SubdomainMap,
+    ) -> Result<()> {
+        // The CDX API of a mirror may be given relative to its base URL
+        let mut query = self.endpoint.url(&collection.cdx_api)?;
+        query
+            .query_pairs_mut()
+            .append_pair("url", &cfg.root_domain)
+            .append_pair("matchType", "domain")
+            .append_pair("output", "json")
+            .append_pair("fl", "url");
+
+        let mut count_url = query.clone();
+        count_url
+            .query_pairs_mut()
+            .append_pair("showNumPages", "true");
+        let Some(text) = fetch_text(cfg, ctx, "Common Crawl page count", &count_url)? else {
+            return Ok(());
+        };
+        let count: PageCount =
+            serde_json::from_str(&text).context("Failed to parse Common Crawl page count")?;
+
+        let pages = count.pages.min(MAX_PAGES_PER_INDEX);
+        if count.pages > MAX_PAGES_PER_INDEX {
+            ctx.warn(format!(
+                "Common Crawl index {} cut off after {} of {} pages",
+                collection.id, MAX_PAGES_PER_INDEX, count.pages
+            ));
+        }
+        eprintln!(
+            "[~] Reading {} result page(s) of Common Crawl index {}",
+            pages, collection.id
+        );
+
+        for page in 0..pages {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            let mut page_url = query.clone();
+            page_url
+                .query_pairs_mut()
+                .append_pair("page", &page.to_string());
+            let Some(text) = fetch_text(cfg, ctx, "Common Crawl index query", &page_url)? else {
+                break;
+            };
+
+            for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                let Ok(capture) = serde_json::from_str::<Capture>(line) else {
+                    continue;
+                };
+                let Ok(url) = Url::parse(&capture.url) else {
+                    continue;
+                };
+                if map.add_url(&url, &cfg.root_domain, self.name()) {
+                    eprintln!(
+                        "{} Discovered potential (sub)domain {} via Common Crawl",
+                        "[+]".green().bold(),
+                        url.host_str().unwrap_or_default().bold()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// GET `url` through the retry policy. The index answers 404 when it has no
+/// captures for the query, which is reported as `None`.
+fn fetch_text(
+    cfg: &DiscoveryConfig,
+    ctx: &SourceContext,
+    what: &str,
+    url: &Url,
+) -> Result<Option<String>> {
+    cfg.retry.run(ctx, what, || {
+        let resp = cfg
+            .http
+            .get(url.as_str())
+            .timeout(cfg.http.timeout().max(MIN_QUERY_TIMEOUT))
+            .send()
+            .with_context(|| format!("failed to send {} request", what))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow::Error::from(HttpStatusError::from_response(&resp))
+                .context(format!("{} returned non-success status code", what)));
+        }
+
+        resp.text()
+            .with_context(|| format!("Failed to read {} response body", what))
+            .map(Some)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::http::stub_server::{self, response};
+
+    #[test]
+    fn test_queries_newest_indexes_page_by_page() {
+        let port = stub_server::spawn(|req, _| {
+            let path = req.path.as_str();
+            if path == "/collinfo.json" {
+                let body = json!([
+                    {"id": "CC-MAIN-2024-33", "cdx-api": "CC-MAIN-2024-33-index"},
+                    {"id": "CC-MAIN-2024-30", "cdx-api": "CC-MAIN-2024-30-index"},
+                    {"id": "CC-MAIN-2024-26", "cdx-api": "CC-MAIN-2024-26-index"}
+                ]);
+                return response("200 OK", &[], &body.to_string());
+            }
+            if !path.contains("matchType=domain") || path.contains("2024-26") {
+                return response("400 Bad Request", &[], "");
+            }
+            if path.contains("2024-30") {
+                return response("404 Not Found", &[], "No Captures found");
+            }
+            if path.contains("showNumPages=true") {
+                return response("200 OK", &[], r#"{"pages": 2, "pageSize": 5, "blocks": 7}"#);
+            }
+            let body = if path.contains("page=0") {
+                "{\"url\": \"https://www.example.com/about?x=1\"}\n\
+                 {\"url\": \"https://other.org/\"}\n"
+            } else {
+                "{\"url\": \"http://shop.example.com/cart\"}\n"
+            };
+            response("200 OK", &[], body)
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            None,
+        )
+        .unwrap();
+
+        let map = CommonCrawl::new(endpoint, 2)
+            .discover(&cfg, &SourceContext::new())
+            .unwrap();
+        assert_eq!(map.hosts(), vec!["shop.example.com", "www.example.com"]);
+        assert!(map.get("www.example.com").unwrap().paths.contains("/about"));
+        assert!(map.get("shop.example.com").unwrap().paths.contains("/cart"));
+    }
+
+    #[test]
+    fn test_warns_when_index_has_more_pages_than_read() {
+        let port = stub_server::spawn(|req, _| {
+            let path = req.path.as_str();
+            if path == "/collinfo.json" {
+                let body = json!([{"id": "CC-MAIN-2024-33", "cdx-api": "CC-MAIN-2024-33-index"}]);
+                return response("200 OK", &[], &body.to_string());
+            }
+            if path.contains("showNumPages=true") {
+                return response("200 OK", &[], r#"{"pages": 80}"#);
+            }
+            response("200 OK", &[], "{\"url\": \"https://www.example.com/\"}\n")
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            None,
+        )
+        .unwrap();
+
+        let ctx = SourceContext::new();
+        let map = CommonCrawl::new(endpoint, 1).discover(&cfg, &ctx).unwrap();
+        assert_eq!(map.hosts(), vec!["www.example.com"]);
+        assert_eq!(
+            ctx.take_warnings(),
+            vec!["Common Crawl index CC-MAIN-2024-33 cut off after 50 of 80 pages"]
+        );
+    }
+}
@@ -4,10 +4,10 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use serde::Deserialize;
 use std::collections::HashSet;
-use std::time::Duration;
 use url::Url;
 
-use crate::sources::{DiscoveryConfig, SubdomainSource};
+use crate::http::HttpStatusError;
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
 use crate::subdomains::SubdomainMap;
 
 pub struct CrtSh;
@@ -27,43 +27,48 @@ struct CrtShEntry {
 
 impl SubdomainSource for CrtSh {
     fn name(&self) -> &'static str {
-        "crt.sh"
+        "crtsh"
     }
 
-    fn discover(&self, cfg: &DiscoveryConfig) -> Result<SubdomainMap> {
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
         // crt.sh query syntax: %.example.com returns all subdomains
         let query_url = format!("https://crt.sh/?q={}&output=json", cfg.root_domain);
 
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(20))
-            .user_agent("subrapid-knightchaser/0.1")
-            .build()
-            .context("Failed to build HTTP client for crt.sh")?;
-
         eprintln!("[*] Querying crt.sh for domain {}", cfg.root_domain);
-        let resp = client.get(&query_url).send().with_context(|| {
-            format!(
-                "Failed to send request to crt.sh for domain {}",
-                cfg.root_domain
-            )
+        let entries = cfg.retry.run(ctx, "crt.sh query", || {
+            let resp = cfg.http.get(&query_url).send().with_context(|| {
+                format!(
+                    "Failed to send request to crt.sh for domain {}",
+                    cfg.root_domain
+                )
+            })?;
+
+            if !resp.status().is_success() {
+                return Err(anyhow::Error::from(HttpStatusError::from_response(&resp))
+                    .context("crt.sh returned non-success status code"));
+            }
+            eprintln!(
+                "[*] Successfully retrieved data from crt.sh, size {} bytes",
+                resp.content_length().unwrap_or(0)
+            );
+
+            // Parse the JSON list from crt.sh
+            resp.json::<Vec<CrtShEntry>>()
+                .context("Failed to parse JSON response from crt.sh")
         })?;
 
-        if !resp.status().is_success() {
-            anyhow::bail!("crt.sh returned non-success status code: {}", resp.status());
-        }
-        eprintln!(
-            "[*] Successfully retrieved data from crt.sh, size {} bytes",
-            resp.content_length().unwrap_or(0)
-        );
-
-        // Parse the JSON list from crt.sh
-        let entries: Vec<CrtShEntry> = resp
-            .json()
-            .context("Failed to parse JSON response from crt.sh")?;
         let mut map: SubdomainMap = SubdomainMap::new();
         let mut seen_domains: HashSet<String> = HashSet::new();
 
         for entry in entries {
+            if ctx.is_cancelled() {
+                break;
+            }
+
             for raw_domain in entry.name_value.split('\n') {
                 let domain = raw_domain.trim();
 
@@ -83,11 +88,11 @@ impl SubdomainSource for CrtSh {
                 // When all subdomain is being printed out,
                 // such scheme is not shown, so this may be acceptable.
                 if let Ok(fake_url) = Url::parse(&format!("https://{}", domain)) {
-                    map.add_url(&fake_url, &cfg.root_domain);
+                    map.add_url(&fake_url, &cfg.root_domain, self.name());
                     eprintln!(
                         "{} Discovered potential (sub)domain {} via crt.sh",
                         "[+]".green().bold(),
-                        format!("{}", domain).bold()
+                        domain.bold()
                     );
                 }
             }
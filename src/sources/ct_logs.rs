@@ -0,0 +1,262 @@
+// src/sources/ct_logs.rs
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+
+use anyhow::Result;
+use colored::Colorize;
+use url::Url;
+
+use crate::ctlog::{Checkpoints, CtLog};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::{Evidence, SubdomainMap};
+use crate::tls::certificate_names;
+
+/// Entries requested per `get-entries` call (logs often cap it lower).
+const BATCH_SIZE: u64 = 256;
+
+/// Entries scanned per log and run unless configured otherwise.
+pub const DEFAULT_MAX_ENTRIES: u64 = 10_000;
+
+/// Reads certificates straight from Certificate Transparency logs (RFC 6962)
+/// instead of going through crt.sh.
+///
+/// Logs are far too large to scan in full, so each run covers at most
+/// `max_entries` per log: the newest ones on the first run, then onwards from
+/// the checkpoint the previous run left behind.
+pub struct CtLogs {
+    logs: Vec<CtLog>,
+    checkpoint_file: Option<PathBuf>,
+    max_entries: u64,
+}
+
+impl CtLogs {
+    pub fn new(logs: Vec<CtLog>, checkpoint_file: Option<PathBuf>, max_entries: u64) -> Self {
+        Self {
+            logs,
+            checkpoint_file,
+            max_entries,
+        }
+    }
+}
+
+impl SubdomainSource for CtLogs {
+    fn name(&self) -> &'static str {
+        "ct-logs"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        let checkpoints = match &self.checkpoint_file {
+            Some(path) => Checkpoints::load(path)?,
+            None => Checkpoints::default(),
+        };
+        let checkpoints = Mutex::new(checkpoints);
+        let map = Mutex::new(SubdomainMap::new());
+
+        let errors: Vec<anyhow::Error> = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .logs
+                .iter()
+                .map(|log| scope.spawn(|| self.scan_log(log, cfg, ctx, &map, &checkpoints)))
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|h| h.join().unwrap().err())
+                .collect()
+        });
+
+        // Progress made before a failure or cancellation is kept as well
+        checkpoints.into_inner().unwrap().save()?;
+
+        if !errors.is_empty() && errors.len() == self.logs.len() {
+            let first = errors.into_iter().next().unwrap();
+            return Err(first.context("every CT log failed"));
+        }
+        for e in errors {
+            ctx.warn(format!("{:#}", e));
+        }
+
+        Ok(map.into_inner().unwrap())
+    }
+}
+
+impl CtLogs {
+    fn scan_log(
+        &self,
+        log: &CtLog,
+        cfg: &DiscoveryConfig,
+        ctx: &SourceContext,
+        map: &Mutex<SubdomainMap>,
+        checkpoints: &Mutex<Checkpoints>,
+    ) -> Result<()> {
+        let root_domain = cfg.root_domain.as_str();
+
+        let tree_size = cfg
+            .retry
+            .run(ctx, "CT get-sth", || log.tree_size(&cfg.http))?;
+        let start = checkpoints
+            .lock()
+            .unwrap()
+            .get(root_domain, log)
+            .unwrap_or_else(|| tree_size.saturating_sub(self.max_entries));
+        let end = tree_size.min(start.saturating_add(self.max_entries));
+
+        eprintln!(
+            "[*] Scanning CT log {} entries {}..{} (tree size {})",
+            log.url, start, end, tree_size
+        );
+
+        let mut next = start;
+        let mut unparsable = 0;
+        while next < end && !ctx.is_cancelled() {
+            let last = (next + BATCH_SIZE).min(end) - 1;
+            let batch = cfg
+                .retry
+                .run(ctx, "CT get-entries", || log.entries(&cfg.http, next, last))?;
+            if batch.is_empty() {
+                anyhow::bail!("{} returned no entries for {}..={}", log.url, next, last);
+            }
+
+            let mut map = map.lock().unwrap();
+            for (index, cert) in (next..).zip(&batch) {
+                let Ok(cert) = cert else {
+                    unparsable += 1;
+                    continue;
+                };
+
+                for (name, _) in certificate_names(cert).in_scope(root_domain) {
+                    let Ok(url) = Url::parse(&format!("https://{}/", name)) else {
+                        continue;
+                    };
+                    if map.add_url(&url, root_domain, self.name()) {
+                        eprintln!(
+                            "{} Discovered (sub)domain {} in CT log {}",
+                            "[+]".green().bold(),
+                            name.bold(),
+                            log.url
+                        );
+                    }
+                    map.record_evidence(
+                        &name,
+                        Evidence {
+                            via: "ct-log".to_string(),
+                            found_on: log.entry_url(index),
+                        },
+                    );
+                }
+            }
+            drop(map);
+
+            next += batch.len() as u64;
+            checkpoints.lock().unwrap().set(root_domain, log, next);
+        }
+
+        if unparsable > 0 {
+            ctx.warn(format!(
+                "{}: skipped {} entries that could not be parsed",
+                log.url, unparsable
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::ctlog::encode_entry;
+    use crate::http::stub_server::{self, response};
+    use crate::tls::test_server;
+
+    /// A log of five entries serving at most two per `get-entries` call.
+    fn mock_log() -> u16 {
+        let entries: Vec<(String, String)> = [
+            ("old.example.com", false),
+            ("www.example.com", false),
+            ("other.org", true),
+            ("staging.example.com", true),
+            ("*.cdn.example.com", false),
+        ]
+        .iter()
+        .map(|(name, precert)| {
+            let (_, cert) = test_server::self_signed(name, &[name]);
+            encode_entry(&cert, *precert)
+        })
+        .collect();
+
+        stub_server::spawn(move |req, _| {
+            let url = Url::parse(&format!("http://log{}", req.path)).unwrap();
+            match url.path() {
+                "/log/ct/v1/get-sth" => response(
+                    "200 OK",
+                    &[],
+                    &json!({"tree_size": entries.len()}).to_string(),
+                ),
+                "/log/ct/v1/get-entries" => {
+                    let param = |name: &str| -> usize {
+                        url.query_pairs()
+                            .find(|(k, _)| k == name)
+                            .map(|(_, v)| v.parse().unwrap())
+                            .unwrap()
+                    };
+                    let (start, end) = (param("start"), param("end").min(param("start") + 1));
+                    let batch: Vec<_> = entries[start..=end]
+                        .iter()
+                        .map(|(leaf, extra)| json!({"leaf_input": leaf, "extra_data": extra}))
+                        .collect();
+                    response("200 OK", &[], &json!({"entries": batch}).to_string())
+                }
+                _ => response("404 Not Found", &[], ""),
+            }
+        })
+    }
+
+    #[test]
+    fn test_scans_mock_log_and_resumes_from_checkpoint() {
+        let port = mock_log();
+        let log = CtLog::parse(&format!("http://127.0.0.1:{}/log", port)).unwrap();
+        let checkpoint_file =
+            std::env::temp_dir().join(format!("subrapid-ct-source-{}.json", std::process::id()));
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+
+        // First run only covers the newest three entries
+        let source = CtLogs::new(vec![log.clone()], Some(checkpoint_file.clone()), 3);
+        let map = source.discover(&cfg, &SourceContext::new()).unwrap();
+        assert_eq!(map.hosts(), vec!["staging.example.com"]);
+        assert_eq!(
+            map.get("staging.example.com")
+                .unwrap()
+                .evidence
+                .first()
+                .unwrap()
+                .found_on,
+            log.entry_url(3)
+        );
+        assert_eq!(
+            Checkpoints::load(&checkpoint_file)
+                .unwrap()
+                .get("example.com", &log),
+            Some(5)
+        );
+
+        // Nothing new since the checkpoint
+        let map = source.discover(&cfg, &SourceContext::new()).unwrap();
+        assert!(map.hosts().is_empty());
+
+        // Rewind the checkpoint to pick up the older entries
+        let mut checkpoints = Checkpoints::load(&checkpoint_file).unwrap();
+        checkpoints.set("example.com", &log, 0);
+        checkpoints.save().unwrap();
+        let map = source.discover(&cfg, &SourceContext::new()).unwrap();
+        assert_eq!(map.hosts(), vec!["old.example.com", "www.example.com"]);
+
+        std::fs::remove_file(&checkpoint_file).unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+// src/sources/dns_bruteforce.rs
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use anyhow::Result;
+use colored::Colorize;
+use url::Url;
+
+use crate::dns::{self, Resolver};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+/// Actively resolves `<word>.<root_domain>` for every word in a wordlist.
+pub struct DnsBruteforce {
+    words: Vec<String>,
+}
+
+impl DnsBruteforce {
+    pub fn new(words: Vec<String>) -> Self {
+        Self { words }
+    }
+}
+
+impl SubdomainSource for DnsBruteforce {
+    fn name(&self) -> &'static str {
+        "dns-bruteforce"
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        dns::ensure_unproxied(&cfg.resolvers, cfg.http.proxy(), self.name())?;
+
+        eprintln!(
+            "[*] Resolving {} candidate names under {} using {} resolver(s)",
+            self.words.len(),
+            cfg.root_domain,
+            cfg.resolvers.len()
+        );
+
+        let resolver = Resolver::new(cfg.resolvers.clone(), dns::DEFAULT_TIMEOUT);
+        let next_word = AtomicUsize::new(0);
+        let map = Mutex::new(SubdomainMap::new());
+
+        thread::scope(|scope| {
+            for _ in 0..cfg.workers.max(1) {
+                scope.spawn(|| {
+                    while !ctx.is_cancelled() {
+                        let idx = next_word.fetch_add(1, Ordering::Relaxed);
+                        let Some(word) = self.words.get(idx) else {
+                            break;
+                        };
+
+                        let candidate = format!("{}.{}", word, cfg.root_domain);
+                        match resolver.resolve(&candidate) {
+                            Ok(Some(lookup)) => {
+                                let Ok(url) = Url::parse(&format!("https://{}", candidate)) else {
+                                    continue;
+                                };
+                                let mut map = map.lock().unwrap();
+                                let is_new = map.add_url(&url, &cfg.root_domain, self.name());
+                                map.record_answers(&candidate, &lookup.addrs);
+                                if is_new {
+                                    eprintln!(
+                                        "{} Discovered potential (sub)domain {} via DNS bruteforce",
+                                        "[+]".green().bold(),
+                                        candidate.bold()
+                                    );
+                                }
+                            }
+                            Ok(None) => {}
+                            Err(e) => {
+                                eprintln!(
+                                    "{} Failed to resolve {}: {}",
+                                    "[!]".red().bold(),
+                                    candidate,
+                                    e
+                                );
+                                ctx.warn(format!("{}: {:#}", candidate, e));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(map.into_inner().unwrap())
+    }
+}
+
+/// Turn a raw wordlist into candidate labels, skipping blanks and `#` comments.
+pub fn parse_wordlist(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = text
+        .lines()
+        .map(|l| l.trim().trim_matches('.').to_lowercase())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+
+    words.sort();
+    words.dedup();
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::dns::stub_server;
+
+    #[test]
+    fn test_bruteforce_against_stub_dns() {
+        let server = stub_server::spawn(HashMap::from([
+            ("www.example.com".to_string(), Ipv4Addr::new(10, 0, 0, 1)),
+            ("mail.example.com".to_string(), Ipv4Addr::new(10, 0, 0, 2)),
+        ]));
+
+        let cfg = DiscoveryConfig {
+            workers: 4,
+            resolvers: vec![server],
+            ..DiscoveryConfig::for_test("https://example.com")
+        };
+
+        let source = DnsBruteforce::new(parse_wordlist("www\nmail\n# comment\n\ndev\nftp\n"));
+        let map = source.discover(&cfg, &SourceContext::new()).unwrap();
+
+        assert_eq!(map.hosts(), vec!["mail.example.com", "www.example.com"]);
+    }
+}
@@ -0,0 +1,137 @@
+// src/sources/hackertarget.rs
+
+use anyhow::Result;
+
+use crate::sources::api::{self, ApiEndpoint};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.hackertarget.com/";
+
+/// Host records from HackerTarget's host search.
+///
+/// The API answers in one go (`host,ip` per line) and reports errors such as
+/// an exhausted quota as plain text with status 200.
+pub struct HackerTarget {
+    endpoint: ApiEndpoint,
+}
+
+impl HackerTarget {
+    pub fn new(endpoint: ApiEndpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl SubdomainSource for HackerTarget {
+    fn name(&self) -> &'static str {
+        "hackertarget"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        eprintln!("[*] Querying HackerTarget for domain {}", cfg.root_domain);
+
+        let mut url = self.endpoint.url("hostsearch/")?;
+        url.query_pairs_mut().append_pair("q", &cfg.root_domain);
+        if let Some(key) = &self.endpoint.api_key {
+            url.query_pairs_mut().append_pair("apikey", key);
+        }
+
+        let text = api::get(
+            cfg,
+            ctx,
+            "HackerTarget query",
+            || cfg.http.get(url.as_str()),
+            |body| Ok(String::from_utf8_lossy(body).into_owned()),
+        )?;
+
+        let mut map = SubdomainMap::new();
+        let lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        // A body without "host,ip" rows is an error message
+        if let Some(first) = lines.clone().next()
+            && !first.contains(',')
+        {
+            if first.to_lowercase().contains("no records found") {
+                return Ok(map);
+            }
+            anyhow::bail!("HackerTarget: {}", first);
+        }
+
+        for line in lines {
+            let host = line.split(',').next().unwrap_or_default();
+            api::record_host(&mut map, host, cfg, self.name());
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::stub_server::{self, response};
+
+    #[test]
+    fn test_parses_rows_and_reports_api_errors() {
+        let port = stub_server::spawn(|req, _| {
+            let quota = [("X-API-Count", "3"), ("X-API-Quota", "50")];
+            if req.path.contains("apikey=spent") {
+                response(
+                    "200 OK",
+                    &quota,
+                    "API count exceeded - Increase Quota with Membership",
+                )
+            } else {
+                response(
+                    "200 OK",
+                    &quota,
+                    "www.example.com,93.184.216.34\nmail.example.com,10.0.0.1\nevil.org,1.2.3.4\n",
+                )
+            }
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let base = format!("http://127.0.0.1:{}", port);
+
+        let ctx = SourceContext::new();
+        let endpoint = ApiEndpoint::new(DEFAULT_BASE_URL, Some(&base), None).unwrap();
+        let map = HackerTarget::new(endpoint).discover(&cfg, &ctx).unwrap();
+        assert_eq!(map.hosts(), vec!["mail.example.com", "www.example.com"]);
+        assert_eq!(ctx.quota().map(|q| q.remaining), Some(47));
+
+        let endpoint =
+            ApiEndpoint::new(DEFAULT_BASE_URL, Some(&base), Some("spent".to_string())).unwrap();
+        let err = HackerTarget::new(endpoint)
+            .discover(&cfg, &SourceContext::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("API count exceeded"));
+    }
+
+    #[test]
+    fn test_send_errors_do_not_echo_the_key() {
+        // Nothing listens on a port we bound and released again
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            Some("s3cr3t".to_string()),
+        )
+        .unwrap();
+
+        let err = HackerTarget::new(endpoint)
+            .discover(&cfg, &SourceContext::new())
+            .unwrap_err();
+        assert!(!format!("{:#}", err).contains("s3cr3t"));
+        assert!(!format!("{:?}", err).contains("s3cr3t"));
+    }
+}
@@ -8,11 +8,27 @@ use std::time::Duration;
 use anyhow::Result;
 use url::Url;
 
-use crate::fetch::fetch_page;
+use crate::fetch::{RedirectHop, fetch_file, fetch_page};
+use crate::headers::extract_header_links;
+use crate::http::HttpStatusError;
+use crate::js;
 use crate::logging::{self, CrawlerStats};
-use crate::parse::{extract_csp_links, extract_links};
-use crate::sources::{DiscoveryConfig, SubdomainSource};
-use crate::subdomains::SubdomainMap;
+use crate::parse::{extract_inline_scripts, extract_links};
+use crate::ratelimit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::sitemap::{self, Sitemap};
+use crate::sourcemap::{self, SourceMapRef};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::{Evidence, SubdomainMap, host_in_scope};
+
+/// Source name recorded on every host the crawler finds.
+const SOURCE_NAME: &str = "html-crawler";
+
+/// How many times a page is re-queued after the host throttles us (429/503).
+const MAX_THROTTLE_RETRIES: usize = 3;
+
+/// Sitemaps (indexes included) fetched per host at most.
+const MAX_SITEMAPS_PER_HOST: usize = 20;
 
 pub struct HtmlCrawler;
 
@@ -24,19 +40,34 @@ impl HtmlCrawler {
 
 impl SubdomainSource for HtmlCrawler {
     fn name(&self) -> &'static str {
-        "html-crawler"
+        SOURCE_NAME
     }
 
-    fn discover(&self, cfg: &DiscoveryConfig) -> Result<SubdomainMap> {
-        crawl_html(cfg)
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        crawl_html(cfg, ctx)
     }
 }
 
+/// What the crawler does with a URL it has fetched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Fetch {
+    /// An HTML page: extract links and keep crawling
+    Page,
+    /// A JavaScript file: scan it for hostnames
+    Script,
+    /// A source map: scan the original sources it embeds
+    SourceMap,
+    /// robots.txt: record the paths it names and follow its sitemaps
+    Robots,
+    /// A sitemap or sitemap index
+    Sitemap,
+}
+
 /// Internal shared crawler state.
 /// Protected by Arc<Mutex<...>> in the crawler.
 struct CrawlerState {
     /// Frontier of URLs to crawl
-    queue: VecDeque<Url>,
+    queue: VecDeque<(Url, Fetch)>,
 
     /// Full set of URLs that we have already visited
     visited_urls: HashSet<String>,
@@ -47,38 +78,104 @@ struct CrawlerState {
     /// Limit per host (to avoid overloading)
     max_pages_per_host: usize,
 
+    /// How many non-page resources (scripts, source maps, sitemaps, ...) we
+    /// have fetched per host and kind
+    host_fetch_count: HashMap<(String, Fetch), usize>,
+
+    /// Limit per host for scripts, and separately for source maps
+    max_scripts_per_host: usize,
+
+    /// Fetch robots.txt for every new host and follow its sitemaps
+    harvest_sitemaps: bool,
+
     /// Discovered subdomains / URLs under the root domain
     sub_map: SubdomainMap,
 
     /// Number of currently active workers
     active: usize,
+
+    /// How often each URL has been re-queued after throttling
+    throttle_retries: HashMap<String, usize>,
 }
 
 impl CrawlerState {
-    fn new(start_url: Url, max_pages_per_host: usize) -> Self {
+    fn new(config: &DiscoveryConfig) -> Self {
+        let start_url = config.start_url.clone();
         let mut queue = VecDeque::new();
         let mut visited_urls = HashSet::new();
         let mut host_page_count = HashMap::new();
 
         if let Some(host) = start_url.host_str() {
             let host = host.to_lowercase();
-            queue.push_back(start_url.clone());
+            queue.push_back((start_url.clone(), Fetch::Page));
             visited_urls.insert(start_url.to_string());
             host_page_count.insert(host, 1);
         } else {
             // No host? Still push the URL, but it won't go far probably.
-            queue.push_back(start_url.clone());
+            queue.push_back((start_url.clone(), Fetch::Page));
             visited_urls.insert(start_url.as_str().to_string());
         }
 
-        Self {
+        let mut state = Self {
             queue,
             visited_urls,
             host_page_count,
-            max_pages_per_host,
+            max_pages_per_host: config.max_pages_per_host,
+            host_fetch_count: HashMap::new(),
+            max_scripts_per_host: config.max_scripts_per_host,
+            harvest_sitemaps: config.harvest_sitemaps,
             sub_map: SubdomainMap::new(),
             active: 0,
+            throttle_retries: HashMap::new(),
+        };
+
+        if state.harvest_sitemaps
+            && let Ok(robots) = start_url.join("/robots.txt")
+        {
+            state.enqueue(robots, Fetch::Robots);
         }
+
+        state
+    }
+
+    /// Queue `url` for fetching unless it was seen before or its host has used
+    /// up the budget for this kind of fetch. Returns whether it was queued.
+    fn enqueue(&mut self, url: Url, kind: Fetch) -> bool {
+        let Some(host) = url.host_str().map(str::to_lowercase) else {
+            return false;
+        };
+
+        let url_str = url.as_str().to_string();
+        if self.visited_urls.contains(&url_str) || !self.take_budget(&host, kind) {
+            return false;
+        }
+
+        self.visited_urls.insert(url_str);
+        self.queue.push_back((url, kind));
+        true
+    }
+
+    /// Count one more fetch of `kind` against `host`, unless its limit is reached.
+    fn take_budget(&mut self, host: &str, kind: Fetch) -> bool {
+        let limit = match kind {
+            Fetch::Page => self.max_pages_per_host,
+            Fetch::Script | Fetch::SourceMap => self.max_scripts_per_host,
+            Fetch::Robots => 1,
+            Fetch::Sitemap => MAX_SITEMAPS_PER_HOST,
+        };
+
+        let count = match kind {
+            Fetch::Page => self.host_page_count.entry(host.to_string()).or_insert(0),
+            _ => self
+                .host_fetch_count
+                .entry((host.to_string(), kind))
+                .or_insert(0),
+        };
+        if *count >= limit {
+            return false;
+        }
+        *count += 1;
+        true
     }
 
     fn snapshot_stats(&self) -> CrawlerStats {
@@ -91,33 +188,38 @@ impl CrawlerState {
 }
 
 enum WorkItem {
-    Url(Url),
+    Url(Url, Fetch),
     Wait,
     Done,
 }
 
 /// Run a multi-threaded crawl and return the final subdomain map.
-pub fn crawl_html(config: &DiscoveryConfig) -> Result<SubdomainMap> {
-    let state = Arc::new(Mutex::new(CrawlerState::new(
-        config.start_url.clone(),
-        config.max_pages_per_host,
-    )));
-
-    let mut handles = Vec::new();
-
-    for worker_id in 0..config.workers {
-        let state = Arc::clone(&state);
-        let root_domain = config.root_domain.clone();
-
-        let handle = thread::spawn(move || {
-            worker_loop(state, &root_domain, worker_id);
-        });
-        handles.push(handle);
-    }
-
-    for handle in handles {
-        handle.join().expect("Worker thread panicked >_<");
-    }
+/// Pages that fail to load are reported through `ctx` and do not stop the crawl.
+/// Requests go through a shared `RateLimiter` built from `config.rate_limit`.
+pub fn crawl_html(config: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+    let state = Arc::new(Mutex::new(CrawlerState::new(config)));
+    let limiter = RateLimiter::new(config.rate_limit);
+
+    // 429/503 are left to the rate limiter, which pauses the whole host
+    let retry = RetryPolicy {
+        retry_throttled: false,
+        ..config.retry
+    };
+    let config = &DiscoveryConfig {
+        retry,
+        ..config.clone()
+    };
+
+    thread::scope(|scope| {
+        for worker_id in 0..config.workers {
+            let state = Arc::clone(&state);
+            let limiter = &limiter;
+
+            scope.spawn(move || {
+                worker_loop(state, config, limiter, worker_id, ctx);
+            });
+        }
+    });
 
     // Pull the sub_map out of the shared state
     let guard = state.lock().unwrap();
@@ -126,15 +228,25 @@ pub fn crawl_html(config: &DiscoveryConfig) -> Result<SubdomainMap> {
 }
 
 /// Start the crawling process from the given start_url.
-fn worker_loop(state: Arc<Mutex<CrawlerState>>, root_domain: &str, worker_id: usize) {
+fn worker_loop(
+    state: Arc<Mutex<CrawlerState>>,
+    config: &DiscoveryConfig,
+    limiter: &RateLimiter,
+    worker_id: usize,
+    ctx: &SourceContext,
+) {
     loop {
+        if ctx.is_cancelled() {
+            break;
+        }
+
         let work = {
             let mut st = state.lock().unwrap();
 
-            if let Some(url) = st.queue.pop_front() {
+            if let Some((url, fetch)) = st.queue.pop_front() {
                 // Take work and mark as active
                 st.active += 1;
-                WorkItem::Url(url)
+                WorkItem::Url(url, fetch)
             } else if st.active > 0 {
                 // No work, but others are active: wait
                 WorkItem::Wait
@@ -145,16 +257,61 @@ fn worker_loop(state: Arc<Mutex<CrawlerState>>, root_domain: &str, worker_id: us
         };
 
         match work {
-            WorkItem::Url(url) => {
-                let res = process_url(&state, &url, root_domain, worker_id);
-                if let Err(e) = res {
+            WorkItem::Url(url, fetch) => {
+                // The fetch functions wait for the limiter before every
+                // request, retries and redirect hops included
+                let host = url.host_str().unwrap_or_default().to_lowercase();
+                let res = match fetch {
+                    Fetch::Page => process_url(&state, &url, config, limiter, worker_id, ctx),
+                    Fetch::Script => process_script(&state, &url, config, limiter, worker_id, ctx),
+                    Fetch::SourceMap => {
+                        process_source_map(&state, &url, config, limiter, worker_id, ctx)
+                    }
+                    Fetch::Robots => process_robots(&state, &url, config, limiter, worker_id, ctx),
+                    Fetch::Sitemap => {
+                        process_sitemap(&state, &url, config, limiter, worker_id, ctx)
+                    }
+                };
+                if res.is_err() && ctx.is_cancelled() {
+                    state.lock().unwrap().active -= 1;
+                    break;
+                }
+
+                let throttled = res.as_ref().err().and_then(|e| {
+                    e.downcast_ref::<HttpStatusError>()
+                        .filter(|status| status.is_throttling())
+                });
+
+                if let Some(status) = throttled {
+                    // Pause the host that answered, which may be a redirect hop
+                    let delay = limiter.throttled(&status.host, status.retry_after);
+
+                    let mut st = state.lock().unwrap();
+                    st.active -= 1;
+                    let retries = st.throttle_retries.entry(url.to_string()).or_insert(0);
+                    *retries += 1;
+                    let give_up = *retries > MAX_THROTTLE_RETRIES;
+                    if !give_up {
+                        st.queue.push_back((url.clone(), fetch));
+                    }
+                    let stats = st.snapshot_stats();
+                    drop(st); // Release lock before logging
+
+                    logging::log_worker_throttled(worker_id, &url, delay, &stats);
+                    if give_up {
+                        ctx.warn(format!("{}: still throttled, giving up ({})", url, status));
+                    }
+                } else if let Err(e) = res {
                     let mut st = state.lock().unwrap();
                     st.active -= 1;
                     let stats = st.snapshot_stats();
                     drop(st); // Release lock before logging
 
                     logging::log_worker_error(worker_id, &url, &e, &stats);
+                    ctx.warn(format!("{}: {:#}", url, e));
                 } else {
+                    limiter.succeeded(&host);
+
                     let mut st = state.lock().unwrap();
                     st.active -= 1;
                     let stats = st.snapshot_stats();
@@ -173,43 +330,302 @@ fn worker_loop(state: Arc<Mutex<CrawlerState>>, root_domain: &str, worker_id: us
     }
 }
 
-/// Check if given host is in scope of the root_domain
-/// Example:
-///     root_domain: `example.com`
-///     in scope: `example.com`, `sub.example.com`
-///     out of scope: `other.com`, `example.org`, `sub.example.org`
-fn host_in_scope(host: &str, root_domain: &str) -> bool {
-    let host = host.to_lowercase();
-    let root = root_domain.to_lowercase();
+fn process_url(
+    state: &Arc<Mutex<CrawlerState>>,
+    url: &Url,
+    config: &DiscoveryConfig,
+    limiter: &RateLimiter,
+    worker_id: usize,
+    ctx: &SourceContext,
+) -> Result<()> {
+    let root_domain = config.root_domain.as_str();
+
+    // Get the page (body + headers)
+    let page = fetch_page(&config.http, limiter, url, root_domain, &config.retry, ctx)?;
+    record_redirects(
+        state,
+        url,
+        &page.final_url,
+        &page.redirects,
+        root_domain,
+        worker_id,
+    );
+    let base = &page.final_url;
+
+    // Extract links from HTML body along with where they came from.
+    // Pages are crawled further, scripts are scanned, other assets only recorded.
+    let mut links: Vec<(Url, String, Option<Fetch>)> = extract_links(&page.body, base)?
+        .into_iter()
+        .map(|link| {
+            let via = link.via();
+            let fetch = if link.is_script() {
+                Some(Fetch::Script)
+            } else if link.is_navigational() {
+                Some(Fetch::Page)
+            } else {
+                None
+            };
+            (link.url, via, fetch)
+        })
+        .collect();
+
+    // Hosts leaking through response headers; redirect targets and CSP
+    // sources are crawled like links, the rest only recorded
+    for link in extract_header_links(&page.headers, base) {
+        let via = link.via();
+        let fetch = match link.header {
+            "location"
+            | "content-location"
+            | "refresh"
+            | "content-security-policy"
+            | "content-security-policy-report-only" => Some(Fetch::Page),
+            _ => None,
+        };
+        links.push((link.url, via, fetch));
+    }
 
-    if host == root {
-        return true;
+    for script in extract_inline_scripts(&page.body) {
+        let found = js::find_references(&script, root_domain);
+        links.extend(
+            found
+                .into_iter()
+                .map(|u| (u, "inline-script".to_string(), None)),
+        );
     }
 
-    let suffix = format!(".{}", root);
-    host.ends_with(&suffix)
+    record_links(state, links, base, root_domain, worker_id);
+    Ok(())
 }
 
-fn process_url(
+/// Fetch a JavaScript file and record every in-scope URL or hostname it mentions.
+/// A referenced source map is queued, or scanned right away if it is inlined.
+fn process_script(
     state: &Arc<Mutex<CrawlerState>>,
     url: &Url,
-    root_domain: &str,
+    config: &DiscoveryConfig,
+    limiter: &RateLimiter,
+    worker_id: usize,
+    ctx: &SourceContext,
+) -> Result<()> {
+    let root_domain = config.root_domain.as_str();
+
+    let script = fetch_page(&config.http, limiter, url, root_domain, &config.retry, ctx)?;
+    record_redirects(
+        state,
+        url,
+        &script.final_url,
+        &script.redirects,
+        root_domain,
+        worker_id,
+    );
+    let base = &script.final_url;
+    let mut links: Vec<(Url, String, Option<Fetch>)> =
+        js::find_references(&script.body, root_domain)
+            .into_iter()
+            .map(|u| (u, "js".to_string(), None))
+            .collect();
+
+    match sourcemap::find_source_map_ref(&script.body, base) {
+        Some(SourceMapRef::Url(map_url)) => {
+            links.push((map_url, "js".to_string(), Some(Fetch::SourceMap)));
+        }
+        Some(SourceMapRef::Inline(map)) => match sourcemap::find_references(&map, root_domain) {
+            Ok(found) => {
+                links.extend(
+                    found
+                        .into_iter()
+                        .map(|u| (u, "source-map".to_string(), None)),
+                );
+            }
+            Err(e) => ctx.warn(format!("{}: inline source map: {:#}", url, e)),
+        },
+        None => {}
+    }
+
+    record_links(state, links, base, root_domain, worker_id);
+    Ok(())
+}
+
+/// Fetch a source map and record hostnames found in the sources it embeds.
+fn process_source_map(
+    state: &Arc<Mutex<CrawlerState>>,
+    url: &Url,
+    config: &DiscoveryConfig,
+    limiter: &RateLimiter,
     worker_id: usize,
+    ctx: &SourceContext,
 ) -> Result<()> {
-    // Get the page (body + CSP)
-    let page = fetch_page(url.as_str())?;
+    let root_domain = config.root_domain.as_str();
+
+    let map = fetch_page(&config.http, limiter, url, root_domain, &config.retry, ctx)?;
+    record_redirects(
+        state,
+        url,
+        &map.final_url,
+        &map.redirects,
+        root_domain,
+        worker_id,
+    );
+    let links = sourcemap::find_references(&map.body, root_domain)?
+        .into_iter()
+        .map(|u| (u, "source-map".to_string(), None))
+        .collect();
+
+    record_links(state, links, &map.final_url, root_domain, worker_id);
+    Ok(())
+}
+
+/// Fetch robots.txt: record the paths it names and queue its sitemaps,
+/// falling back to /sitemap.xml when it declares none.
+fn process_robots(
+    state: &Arc<Mutex<CrawlerState>>,
+    url: &Url,
+    config: &DiscoveryConfig,
+    limiter: &RateLimiter,
+    worker_id: usize,
+    ctx: &SourceContext,
+) -> Result<()> {
+    let root_domain = config.root_domain.as_str();
+
+    let file = fetch_file(
+        &config.http,
+        limiter,
+        url,
+        root_domain,
+        sitemap::MAX_ROBOTS_BYTES,
+        &config.retry,
+        ctx,
+    )?;
+    let (robots, base) = match &file {
+        Some(file) => {
+            record_redirects(
+                state,
+                url,
+                &file.final_url,
+                &file.redirects,
+                root_domain,
+                worker_id,
+            );
+            let robots =
+                sitemap::parse_robots(&String::from_utf8_lossy(&file.body), &file.final_url);
+            (robots, &file.final_url)
+        }
+        None => (Default::default(), url),
+    };
+
+    if robots.sitemaps.is_empty()
+        && let Ok(fallback) = url.join("/sitemap.xml")
+    {
+        // Only a guess, so it is queued without being recorded as a path
+        state.lock().unwrap().enqueue(fallback, Fetch::Sitemap);
+    }
+
+    let links = robots
+        .sitemaps
+        .into_iter()
+        .map(|u| (u, "robots.txt".to_string(), Some(Fetch::Sitemap)))
+        .chain(
+            robots
+                .paths
+                .into_iter()
+                .map(|u| (u, "robots.txt".to_string(), None)),
+        )
+        .collect();
+
+    record_links(state, links, base, root_domain, worker_id);
+    Ok(())
+}
+
+/// Fetch a sitemap: indexes queue further sitemaps, URL sets seed the crawl.
+fn process_sitemap(
+    state: &Arc<Mutex<CrawlerState>>,
+    url: &Url,
+    config: &DiscoveryConfig,
+    limiter: &RateLimiter,
+    worker_id: usize,
+    ctx: &SourceContext,
+) -> Result<()> {
+    let root_domain = config.root_domain.as_str();
+
+    let Some(file) = fetch_file(
+        &config.http,
+        limiter,
+        url,
+        root_domain,
+        sitemap::MAX_SITEMAP_BYTES,
+        &config.retry,
+        ctx,
+    )?
+    else {
+        return Ok(());
+    };
+    record_redirects(
+        state,
+        url,
+        &file.final_url,
+        &file.redirects,
+        root_domain,
+        worker_id,
+    );
+
+    let base = &file.final_url;
+    let links = match sitemap::parse_sitemap(&file.body, base)? {
+        Sitemap::Index(sitemaps) => sitemaps
+            .into_iter()
+            .map(|u| (u, "sitemap".to_string(), Some(Fetch::Sitemap)))
+            .collect(),
+        Sitemap::UrlSet(pages) => pages
+            .into_iter()
+            .map(|u| (u, "sitemap".to_string(), Some(Fetch::Page)))
+            .collect(),
+    };
+
+    record_links(state, links, base, root_domain, worker_id);
+    Ok(())
+}
 
-    // Extract links from HTML body (standard crawling)
-    let mut links = extract_links(&page.body, url)?;
+/// Record every host a redirect chain passed through, with the redirecting URL
+/// as evidence, attach the chain to those hosts and mark the final URL as
+/// visited so it is not fetched a second time.
+fn record_redirects(
+    state: &Arc<Mutex<CrawlerState>>,
+    url: &Url,
+    final_url: &Url,
+    redirects: &[RedirectHop],
+    root_domain: &str,
+    worker_id: usize,
+) {
+    if redirects.is_empty() {
+        return;
+    }
 
-    if let Some(csp_header) = page.csp {
-        let csp_links = extract_csp_links(&csp_header);
-        links.extend(csp_links);
+    for hop in redirects {
+        let via = format!("redirect:{}", hop.status.as_u16());
+        let links = vec![(hop.to.clone(), via, None)];
+        record_links(state, links, &hop.from, root_domain, worker_id);
     }
 
+    let chain: Vec<Url> = std::iter::once(url.clone())
+        .chain(redirects.iter().map(|hop| hop.to.clone()))
+        .collect();
+
     let mut st = state.lock().unwrap();
+    st.visited_urls.insert(final_url.to_string());
+    st.sub_map.record_redirect_chain(&chain);
+}
 
-    for link in links {
+/// Record `(url, via, fetch)` links found on `found_on` and queue the ones
+/// worth fetching, within the per-host budgets.
+fn record_links(
+    state: &Arc<Mutex<CrawlerState>>,
+    links: Vec<(Url, String, Option<Fetch>)>,
+    found_on: &Url,
+    root_domain: &str,
+    worker_id: usize,
+) {
+    let mut st = state.lock().unwrap();
+
+    for (link, via, fetch) in links {
         let host = match link.host_str() {
             Some(h) => h.to_lowercase(),
             None => continue,
@@ -222,7 +638,14 @@ fn process_url(
 
         // Always record in the subdomain map, even if we don't crawl the page
         // And check if this host is newly discovered
-        let is_new_host = st.sub_map.add_url(&link, root_domain);
+        let is_new_host = st.sub_map.add_url(&link, root_domain, SOURCE_NAME);
+        st.sub_map.record_evidence(
+            &host,
+            Evidence {
+                via,
+                found_on: found_on.to_string(),
+            },
+        );
 
         // Announce new subdomain (host != root_domain)
         let root = root_domain.to_lowercase();
@@ -232,23 +655,184 @@ fn process_url(
             logging::log_new_subdomain(worker_id, &host, root_domain, &stats);
         }
 
-        // Decide whether to crawl this URL or not
-        let url_str = link.as_str().to_string();
-        if st.visited_urls.contains(&url_str) {
-            continue;
+        // New hosts get their robots.txt (and through it, sitemaps) harvested
+        if is_new_host
+            && st.harvest_sitemaps
+            && let Ok(robots) = link.join("/robots.txt")
+        {
+            st.enqueue(robots, Fetch::Robots);
         }
 
-        // Read the current count by value (ends the immutable borrow here)
-        let current_count = st.host_page_count.get(&host).copied().unwrap_or(0);
-        if current_count >= st.max_pages_per_host {
-            continue;
+        // Decide whether to fetch this URL or not
+        if let Some(fetch) = fetch {
+            st.enqueue(link, fetch);
         }
+    }
+}
 
-        let count = st.host_page_count.entry(host.clone()).or_insert(0);
-        *count += 1;
-        st.visited_urls.insert(url_str);
-        st.queue.push_back(link);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::stub_server::{self, response};
+    use crate::http::{HttpClient, HttpSettings};
+    use crate::proxy::{BypassList, ProxyMode};
+
+    /// A crawl of `start_url` whose requests all go to the stub server on
+    /// `port`, acting as a proxy, so any hostname can be served.
+    fn proxied_config(start_url: &str, port: u16) -> DiscoveryConfig {
+        let proxy = ProxyMode::parse(&format!("http://127.0.0.1:{port}"), &BypassList::default());
+        DiscoveryConfig {
+            http: HttpClient::new(&HttpSettings {
+                proxy: proxy.unwrap(),
+                ..Default::default()
+            })
+            .unwrap(),
+            ..DiscoveryConfig::for_test(start_url)
+        }
     }
 
-    Ok(())
+    #[test]
+    fn test_records_redirect_chain_and_evidence_on_every_hop() {
+        let port = stub_server::spawn(|req, _| match req.path.as_str() {
+            "http://www.example.com/" => response(
+                "301 Moved Permanently",
+                &[("Location", "http://sso.example.com/login")],
+                "",
+            ),
+            "http://sso.example.com/login" => response(
+                "302 Found",
+                &[("Location", "http://app.example.com/home")],
+                "",
+            ),
+            "http://app.example.com/home" => response("200 OK", &[], "<html></html>"),
+            _ => response("404 Not Found", &[], ""),
+        });
+
+        let cfg = proxied_config("http://www.example.com/", port);
+        let map = crawl_html(&cfg, &SourceContext::new()).unwrap();
+        assert_eq!(map.hosts(), vec!["app.example.com", "sso.example.com"]);
+
+        let chain = vec![
+            "http://www.example.com/".to_string(),
+            "http://sso.example.com/login".to_string(),
+            "http://app.example.com/home".to_string(),
+        ];
+        let hops = [
+            ("sso.example.com", "redirect:301", "http://www.example.com/"),
+            (
+                "app.example.com",
+                "redirect:302",
+                "http://sso.example.com/login",
+            ),
+        ];
+        for (host, via, found_on) in hops {
+            let entry = map.get(host).unwrap();
+            assert!(entry.redirect_chains.contains(&chain), "{host}");
+            assert!(
+                entry.evidence.contains(&Evidence {
+                    via: via.to_string(),
+                    found_on: found_on.to_string(),
+                }),
+                "{host}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_scans_scripts_source_maps_robots_and_sitemaps() {
+        let port = stub_server::spawn(|req, _| match req.path.as_str() {
+            "http://www.example.com/" => response(
+                "200 OK",
+                &[],
+                r#"<html><script src="/static/app.js"></script></html>"#,
+            ),
+            "http://www.example.com/static/app.js" => response(
+                "200 OK",
+                &[],
+                "fetch('http://api.example.com/v1/me');\n//# sourceMappingURL=app.js.map\n",
+            ),
+            "http://www.example.com/static/app.js.map" => response(
+                "200 OK",
+                &[],
+                r#"{"version":3,"sources":["src/api.ts"],
+                    "sourcesContent":["const BASE = 'http://internal.example.com/graphql';"]}"#,
+            ),
+            "http://www.example.com/robots.txt" => response(
+                "301 Moved Permanently",
+                &[("Location", "http://static.example.com/robots.txt")],
+                "",
+            ),
+            "http://static.example.com/robots.txt" => response(
+                "200 OK",
+                &[],
+                "User-agent: *\nDisallow: /admin/\nSitemap: /sitemap.xml\n",
+            ),
+            "http://static.example.com/sitemap.xml" => response(
+                "200 OK",
+                &[],
+                "<urlset><url><loc>http://blog.example.com/post</loc></url></urlset>",
+            ),
+            "http://blog.example.com/post" => response("200 OK", &[], "<html></html>"),
+            _ => response("404 Not Found", &[], ""),
+        });
+
+        let cfg = DiscoveryConfig {
+            harvest_sitemaps: true,
+            ..proxied_config("http://www.example.com/", port)
+        };
+        let map = crawl_html(&cfg, &SourceContext::new()).unwrap();
+        assert_eq!(
+            map.hosts(),
+            vec![
+                "api.example.com",
+                "blog.example.com",
+                "internal.example.com",
+                "static.example.com",
+                "www.example.com"
+            ]
+        );
+        assert!(
+            map.get("static.example.com")
+                .unwrap()
+                .paths
+                .contains("/admin/")
+        );
+
+        let found = [
+            (
+                "api.example.com",
+                "js",
+                "http://www.example.com/static/app.js",
+            ),
+            (
+                "internal.example.com",
+                "source-map",
+                "http://www.example.com/static/app.js.map",
+            ),
+            (
+                "static.example.com",
+                "redirect:301",
+                "http://www.example.com/robots.txt",
+            ),
+            (
+                "static.example.com",
+                "robots.txt",
+                "http://static.example.com/robots.txt",
+            ),
+            (
+                "blog.example.com",
+                "sitemap",
+                "http://static.example.com/sitemap.xml",
+            ),
+        ];
+        for (host, via, found_on) in found {
+            assert!(
+                map.get(host).unwrap().evidence.contains(&Evidence {
+                    via: via.to_string(),
+                    found_on: found_on.to_string(),
+                }),
+                "{host}"
+            );
+        }
+    }
 }
@@ -1,12 +1,30 @@
 // src/sources/mod.rs
 
+pub mod api;
+pub mod certspotter;
+pub mod commoncrawl;
 pub mod crtsh;
+pub mod ct_logs;
+pub mod dns_bruteforce;
+pub mod hackertarget;
 pub mod html_crawler;
+pub mod otx;
+pub mod registry;
+pub mod tls_san;
+pub mod urlscan;
 pub mod wayback;
 
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
 use anyhow::Result;
+use serde::Serialize;
 use url::Url;
 
+use crate::http::HttpClient;
+use crate::ratelimit::RateLimitSettings;
+use crate::retry::RetryPolicy;
 use crate::subdomains::SubdomainMap;
 
 /// Shared config for all discovery strategies.
@@ -23,6 +41,145 @@ pub struct DiscoveryConfig {
 
     /// Maximum number of pages to crawl per host.
     pub max_pages_per_host: usize,
+
+    /// Maximum number of JavaScript files to fetch and scan per host.
+    pub max_scripts_per_host: usize,
+
+    /// Whether the crawler harvests robots.txt and sitemaps of every host it finds.
+    pub harvest_sitemaps: bool,
+
+    /// Throttling for requests the crawler sends to the target.
+    pub rate_limit: RateLimitSettings,
+
+    /// How HTTP requests that fail transiently are retried.
+    pub retry: RetryPolicy,
+
+    /// DNS resolvers used by resolver-based sources.
+    pub resolvers: Vec<SocketAddr>,
+
+    /// Hosts found by the sources that ran before, for follow-up sources
+    /// that probe them (empty for everything else).
+    pub seed_hosts: Vec<String>,
+
+    /// Passive mode: only sources that never contact the target may run.
+    pub passive: bool,
+
+    /// Shared HTTP client (connection pool) for every source and crawler worker.
+    pub http: HttpClient,
+}
+
+#[cfg(test)]
+impl DiscoveryConfig {
+    /// A single-worker config scoped to the root domain of `start_url`.
+    pub(crate) fn for_test(start_url: &str) -> Self {
+        let start_url = Url::parse(start_url).unwrap();
+        let root_domain =
+            crate::subdomains::extract_root_domain(start_url.host_str().unwrap()).unwrap();
+
+        Self {
+            start_url,
+            root_domain,
+            workers: 1,
+            max_pages_per_host: 5,
+            max_scripts_per_host: 5,
+            harvest_sitemaps: false,
+            rate_limit: RateLimitSettings::default(),
+            retry: RetryPolicy::default(),
+            resolvers: Vec::new(),
+            seed_hosts: Vec::new(),
+            passive: false,
+            // Tests talk to local stub servers, never through the environment's proxy
+            http: HttpClient::new(&crate::http::HttpSettings {
+                proxy: crate::proxy::ProxyMode::Direct,
+                ..Default::default()
+            })
+            .unwrap(),
+        }
+    }
+}
+
+/// Per-run state handed to a source while it discovers.
+///
+/// Sources use it to report problems that did not stop them (a page that failed
+/// to load, a name that failed to resolve); any such warning marks the run partial.
+/// Long-running sources should also poll `is_cancelled` and return early with
+/// whatever they have found so far.
+#[derive(Debug, Default)]
+pub struct SourceContext {
+    warnings: Mutex<Vec<String>>,
+    cancelled: AtomicBool,
+    attempts: AtomicUsize,
+    retries: AtomicUsize,
+    quota: Mutex<Option<Quota>>,
+    api_requests: AtomicUsize,
+}
+
+/// Request quota left on a third-party API, as last reported by the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Quota {
+    pub remaining: u64,
+    pub limit: Option<u64>,
+}
+
+impl SourceContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a non-fatal problem.
+    pub fn warn(&self, msg: impl Into<String>) {
+        self.warnings.lock().unwrap().push(msg.into());
+    }
+
+    /// Ask the source to stop as soon as possible.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Count one request attempt (`retry` if it repeats an earlier one).
+    pub fn record_attempt(&self, retry: bool) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+        if retry {
+            self.retries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Request attempts made so far, retries included.
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn retries(&self) -> usize {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// Remember the quota an API reported; the latest report wins.
+    pub fn record_quota(&self, quota: Quota) {
+        *self.quota.lock().unwrap() = Some(quota);
+    }
+
+    pub fn quota(&self) -> Option<Quota> {
+        *self.quota.lock().unwrap()
+    }
+
+    /// Count one request sent to a third-party API, which uses up its quota
+    /// whether or not the API reports how much is left.
+    pub fn record_api_request(&self) {
+        self.api_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn api_requests(&self) -> usize {
+        self.api_requests.load(Ordering::Relaxed)
+    }
+
+    /// Take all warnings recorded so far.
+    pub fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
 }
 
 /// A pluggable source of subdomains (HTML crawling, DNS bruteforce, CT logs, ...).
@@ -30,6 +187,17 @@ pub trait SubdomainSource: Send + Sync {
     /// Returns the name of this discovery source.
     fn name(&self) -> &'static str;
 
+    /// Whether this source only talks to third parties (CT logs, archives, ...).
+    ///
+    /// Anything that sends traffic to the target's own infrastructure, including
+    /// DNS lookups that end up at its name servers, must return false.
+    fn is_passive(&self) -> bool {
+        false
+    }
+
     /// Discovers subdomains according to the given config.
-    fn discover(&self, cfg: &DiscoveryConfig) -> Result<SubdomainMap>;
+    ///
+    /// Returning an error means the source failed outright; problems the source
+    /// could work around should go to `ctx.warn` instead.
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap>;
 }
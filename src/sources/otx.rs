@@ -0,0 +1,164 @@
+// src/sources/otx.rs
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::sources::api::{self, ApiEndpoint};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+pub const DEFAULT_BASE_URL: &str = "https://otx.alienvault.com/";
+
+/// Records requested per page.
+const PAGE_SIZE: usize = 500;
+
+/// Pages fetched at most; a warning says so when results are cut off there.
+const MAX_PAGES: usize = 50;
+
+/// Passive DNS records from AlienVault Open Threat Exchange.
+pub struct AlienVaultOtx {
+    endpoint: ApiEndpoint,
+}
+
+impl AlienVaultOtx {
+    pub fn new(endpoint: ApiEndpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+/// A page of `/api/v1/indicators/domain/<domain>/passive_dns`.
+#[derive(Deserialize, Debug)]
+struct PassiveDns {
+    #[serde(default)]
+    passive_dns: Vec<PassiveDnsRecord>,
+
+    /// Total number of records across all pages
+    #[serde(default)]
+    count: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct PassiveDnsRecord {
+    hostname: String,
+}
+
+impl SubdomainSource for AlienVaultOtx {
+    fn name(&self) -> &'static str {
+        "otx"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        eprintln!(
+            "[*] Querying AlienVault OTX passive DNS for domain {}",
+            cfg.root_domain
+        );
+
+        let mut map = SubdomainMap::new();
+        let mut seen = 0;
+
+        for page_no in 1..=MAX_PAGES {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            let mut url = self.endpoint.url(&format!(
+                "api/v1/indicators/domain/{}/passive_dns",
+                cfg.root_domain
+            ))?;
+            url.query_pairs_mut()
+                .append_pair("limit", &PAGE_SIZE.to_string())
+                .append_pair("page", &page_no.to_string());
+
+            let page: PassiveDns = api::get(
+                cfg,
+                ctx,
+                "OTX query",
+                || {
+                    let req = cfg.http.get(url.as_str());
+                    match &self.endpoint.api_key {
+                        Some(key) => req.header("X-OTX-API-KEY", key),
+                        None => req,
+                    }
+                },
+                |body| {
+                    serde_json::from_slice(body).context("Failed to parse JSON response from OTX")
+                },
+            )?;
+
+            for record in &page.passive_dns {
+                api::record_host(&mut map, &record.hostname, cfg, self.name());
+            }
+
+            // A short page is the last one; `count` catches a full last page
+            seen += page.passive_dns.len();
+            if page.passive_dns.len() < PAGE_SIZE || seen >= page.count {
+                break;
+            }
+            if page_no == MAX_PAGES {
+                ctx.warn(format!(
+                    "OTX results cut off after {} pages ({} of {} records)",
+                    MAX_PAGES, seen, page.count
+                ));
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::http::stub_server::{self, response};
+    use crate::sources::Quota;
+
+    #[test]
+    fn test_paginates_until_count_is_reached() {
+        let port = stub_server::spawn(|req, _| {
+            let page: usize = req
+                .path
+                .split("page=")
+                .nth(1)
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(1);
+            let records: Vec<_> = (0..PAGE_SIZE)
+                .map(|i| json!({"hostname": format!("h{}-{}.example.com", page, i)}))
+                .collect();
+            let body = json!({"passive_dns": records, "count": PAGE_SIZE * 2});
+            response(
+                "200 OK",
+                &[
+                    ("X-RateLimit-Remaining", "9998"),
+                    ("X-RateLimit-Limit", "10000"),
+                ],
+                &body.to_string(),
+            )
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            None,
+        )
+        .unwrap();
+
+        let ctx = SourceContext::new();
+        let map = AlienVaultOtx::new(endpoint).discover(&cfg, &ctx).unwrap();
+        assert_eq!(map.hosts().len(), PAGE_SIZE * 2);
+        assert_eq!(ctx.attempts(), 2);
+        assert_eq!(
+            ctx.quota(),
+            Some(Quota {
+                remaining: 9998,
+                limit: Some(10000)
+            })
+        );
+    }
+}
@@ -0,0 +1,381 @@
+// src/sources/registry.rs
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::ctlog::CtLog;
+use crate::sources::SubdomainSource;
+use crate::sources::api::ApiEndpoint;
+use crate::sources::certspotter::{self, CertSpotter};
+use crate::sources::commoncrawl::{self, CommonCrawl};
+use crate::sources::crtsh::CrtSh;
+use crate::sources::ct_logs::{self, CtLogs};
+use crate::sources::dns_bruteforce::DnsBruteforce;
+use crate::sources::hackertarget::{self, HackerTarget};
+use crate::sources::html_crawler::HtmlCrawler;
+use crate::sources::otx::{self, AlienVaultOtx};
+use crate::sources::tls_san::TlsSan;
+use crate::sources::urlscan::{self, UrlScan};
+use crate::sources::wayback::{self, WaybackArchive};
+
+/// Source-specific settings gathered from the command line.
+#[derive(Clone, Debug, Default)]
+pub struct SourceOptions {
+    /// Candidate labels for DNS bruteforcing, if a wordlist was given.
+    pub wordlist: Option<Vec<String>>,
+
+    /// Ports the tls-san source connects to (its defaults if empty).
+    pub tls_ports: Vec<u16>,
+
+    /// Base URLs of the CT logs the ct-logs source reads.
+    pub ct_logs: Vec<String>,
+
+    /// Where ct-logs keeps its per-log progress, if anywhere.
+    pub ct_checkpoint: Option<PathBuf>,
+
+    /// Entries ct-logs scans per log and run (its default if unset).
+    pub ct_max_entries: Option<u64>,
+
+    /// Whether wayback keeps every archived URL rather than one per host.
+    pub wayback_all_paths: bool,
+
+    /// Crawl indexes commoncrawl queries (its default if unset).
+    pub commoncrawl_indexes: Option<usize>,
+
+    /// Base URL overrides for API sources, by source id (`--source-base-url`).
+    pub base_urls: HashMap<String, String>,
+
+    /// API keys by source id (`--api-key`).
+    pub api_keys: HashMap<String, String>,
+}
+
+impl SourceOptions {
+    /// Endpoint of an API source: its default base URL unless overridden,
+    /// plus its API key if one was given.
+    fn endpoint(&self, id: &str, default_base_url: &str) -> Result<ApiEndpoint> {
+        ApiEndpoint::new(
+            default_base_url,
+            self.base_urls.get(id).map(String::as_str),
+            self.api_keys.get(id).cloned(),
+        )
+    }
+}
+
+/// A registered discovery source.
+pub struct SourceSpec {
+    /// Identifier used on the command line; matches `SubdomainSource::name`.
+    pub id: &'static str,
+
+    /// One-line description for `--list-sources`.
+    pub description: &'static str,
+
+    /// Never contacts the target; must agree with `SubdomainSource::is_passive`.
+    pub passive: bool,
+
+    /// Starts once the other sources are done and probes the hosts they found
+    /// (handed over in `DiscoveryConfig::seed_hosts`).
+    pub follow_up: bool,
+
+    /// Whether the source runs when `--sources` is not given.
+    enabled_by_default: fn(&SourceOptions) -> bool,
+
+    build: fn(&SourceOptions) -> Result<Box<dyn SubdomainSource>>,
+}
+
+impl SourceSpec {
+    pub fn build(&self, opts: &SourceOptions) -> Result<Box<dyn SubdomainSource>> {
+        let src = (self.build)(opts)?;
+        debug_assert_eq!(src.name(), self.id);
+        debug_assert_eq!(src.is_passive(), self.passive);
+        Ok(src)
+    }
+}
+
+/// Every source subrapid knows about, in the order they are started.
+pub const REGISTRY: &[SourceSpec] = &[
+    SourceSpec {
+        id: "html-crawler",
+        description: "Crawls HTML pages starting from the target URL",
+        passive: false,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |_| Ok(Box::new(HtmlCrawler::new())),
+    },
+    SourceSpec {
+        id: "crtsh",
+        description: "Certificate Transparency search via crt.sh",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |_| Ok(Box::new(CrtSh::new())),
+    },
+    SourceSpec {
+        id: "ct-logs",
+        description: "Reads certificates directly from RFC 6962 CT logs (needs --ct-log)",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |opts| !opts.ct_logs.is_empty(),
+        build: |opts| {
+            if opts.ct_logs.is_empty() {
+                anyhow::bail!("ct-logs requires --ct-log");
+            }
+            let logs = opts
+                .ct_logs
+                .iter()
+                .map(|url| CtLog::parse(url))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Box::new(CtLogs::new(
+                logs,
+                opts.ct_checkpoint.clone(),
+                opts.ct_max_entries.unwrap_or(ct_logs::DEFAULT_MAX_ENTRIES),
+            )))
+        },
+    },
+    SourceSpec {
+        id: "wayback",
+        description: "Historical URLs from the Wayback Machine CDX API",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |opts| {
+            let endpoint = opts.endpoint("wayback", wayback::DEFAULT_BASE_URL)?;
+            Ok(Box::new(WaybackArchive::new(
+                endpoint,
+                opts.wayback_all_paths,
+            )))
+        },
+    },
+    SourceSpec {
+        id: "commoncrawl",
+        description: "URLs captured by Common Crawl, from its CDX indexes",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |opts| {
+            let endpoint = opts.endpoint("commoncrawl", commoncrawl::DEFAULT_BASE_URL)?;
+            let max_indexes = opts
+                .commoncrawl_indexes
+                .unwrap_or(commoncrawl::DEFAULT_MAX_INDEXES);
+            Ok(Box::new(CommonCrawl::new(endpoint, max_indexes)))
+        },
+    },
+    SourceSpec {
+        id: "certspotter",
+        description: "Certificate issuances from the CertSpotter API",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |opts| {
+            let endpoint = opts.endpoint("certspotter", certspotter::DEFAULT_BASE_URL)?;
+            Ok(Box::new(CertSpotter::new(endpoint)))
+        },
+    },
+    SourceSpec {
+        id: "otx",
+        description: "Passive DNS from AlienVault OTX",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |opts| {
+            let endpoint = opts.endpoint("otx", otx::DEFAULT_BASE_URL)?;
+            Ok(Box::new(AlienVaultOtx::new(endpoint)))
+        },
+    },
+    SourceSpec {
+        id: "hackertarget",
+        description: "Host search from the HackerTarget API",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |opts| {
+            let endpoint = opts.endpoint("hackertarget", hackertarget::DEFAULT_BASE_URL)?;
+            Ok(Box::new(HackerTarget::new(endpoint)))
+        },
+    },
+    SourceSpec {
+        id: "urlscan",
+        description: "Domains of pages scanned by urlscan.io",
+        passive: true,
+        follow_up: false,
+        enabled_by_default: |_| true,
+        build: |opts| {
+            let endpoint = opts.endpoint("urlscan", urlscan::DEFAULT_BASE_URL)?;
+            Ok(Box::new(UrlScan::new(endpoint)))
+        },
+    },
+    SourceSpec {
+        id: "dns-bruteforce",
+        description: "Resolves wordlist candidates against DNS (needs --wordlist)",
+        passive: false,
+        follow_up: false,
+        enabled_by_default: |opts| opts.wordlist.is_some(),
+        build: |opts| {
+            let Some(words) = &opts.wordlist else {
+                anyhow::bail!("dns-bruteforce requires --wordlist");
+            };
+            Ok(Box::new(DnsBruteforce::new(words.clone())))
+        },
+    },
+    SourceSpec {
+        id: "tls-san",
+        description: "Reads names from the TLS certificates of every host found",
+        passive: false,
+        follow_up: true,
+        enabled_by_default: |_| true,
+        build: |opts| Ok(Box::new(TlsSan::new(opts.tls_ports.clone()))),
+    },
+];
+
+/// Look up a source by id.
+pub fn find(id: &str) -> Option<&'static SourceSpec> {
+    REGISTRY.iter().find(|spec| spec.id == id)
+}
+
+/// Parse an `ID=VALUE` per-source setting such as `--api-key urlscan=...`,
+/// checking that the source exists.
+pub fn parse_source_setting(spec: &str, flag: &str) -> Result<(String, String)> {
+    let Some((id, value)) = spec.split_once('=') else {
+        anyhow::bail!("{} expects ID=VALUE, got '{}'", flag, spec);
+    };
+    let id = id.trim();
+    if find(id).is_none() {
+        anyhow::bail!("unknown source '{}' in {}", id, flag);
+    }
+    Ok((id.to_string(), value.trim().to_string()))
+}
+
+/// Resolve `--sources` / `--exclude-sources` into the list of sources to run.
+///
+/// An empty `include` means "every source enabled by default".
+/// In passive mode active sources are dropped from the defaults, and asking
+/// for one explicitly is an error rather than something we quietly ignore.
+pub fn select(
+    include: &[String],
+    exclude: &[String],
+    passive: bool,
+    opts: &SourceOptions,
+) -> Result<Vec<&'static SourceSpec>> {
+    for id in include.iter().chain(exclude) {
+        if find(id).is_none() {
+            let known: Vec<&str> = REGISTRY.iter().map(|s| s.id).collect();
+            anyhow::bail!("unknown source '{}' (available: {})", id, known.join(", "));
+        }
+    }
+
+    if let Some(id) = include
+        .iter()
+        .find(|id| passive && find(id).is_some_and(|s| !s.passive))
+    {
+        anyhow::bail!("refusing to run active source '{}' in --passive mode", id);
+    }
+
+    let selected: Vec<&'static SourceSpec> = REGISTRY
+        .iter()
+        .filter(|spec| {
+            if include.is_empty() {
+                (spec.enabled_by_default)(opts)
+            } else {
+                include.iter().any(|id| id == spec.id)
+            }
+        })
+        .filter(|spec| !exclude.iter().any(|id| id == spec.id))
+        .filter(|spec| !passive || spec.passive)
+        .collect();
+
+    if selected.is_empty() {
+        anyhow::bail!("no sources selected");
+    }
+
+    Ok(selected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(specs: &[&SourceSpec]) -> Vec<&'static str> {
+        specs.iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn test_select_include_and_exclude() {
+        let opts = SourceOptions::default();
+
+        let all = select(&[], &[], false, &opts).unwrap();
+        assert_eq!(
+            ids(&all),
+            vec![
+                "html-crawler",
+                "crtsh",
+                "wayback",
+                "commoncrawl",
+                "certspotter",
+                "otx",
+                "hackertarget",
+                "urlscan",
+                "tls-san"
+            ]
+        );
+
+        let exclude = ["html-crawler".to_string(), "tls-san".to_string()];
+        let passive = select(&[], &exclude, false, &opts).unwrap();
+        assert_eq!(
+            ids(&passive),
+            vec![
+                "crtsh",
+                "wayback",
+                "commoncrawl",
+                "certspotter",
+                "otx",
+                "hackertarget",
+                "urlscan"
+            ]
+        );
+
+        let only = select(&["wayback".to_string()], &[], false, &opts).unwrap();
+        assert_eq!(ids(&only), vec!["wayback"]);
+
+        assert!(select(&["nope".to_string()], &[], false, &opts).is_err());
+    }
+
+    #[test]
+    fn test_passive_mode_refuses_active_sources() {
+        let opts = SourceOptions {
+            wordlist: Some(vec!["www".to_string()]),
+            ..Default::default()
+        };
+
+        let passive = select(&[], &[], true, &opts).unwrap();
+        assert_eq!(
+            ids(&passive),
+            vec![
+                "crtsh",
+                "wayback",
+                "commoncrawl",
+                "certspotter",
+                "otx",
+                "hackertarget",
+                "urlscan"
+            ]
+        );
+
+        assert!(select(&["html-crawler".to_string()], &[], true, &opts).is_err());
+        assert!(select(&["dns-bruteforce".to_string()], &[], true, &opts).is_err());
+    }
+
+    #[test]
+    fn test_builds_match_ids() {
+        let opts = SourceOptions {
+            wordlist: Some(vec!["www".to_string()]),
+            ct_logs: vec!["https://ct.example.net/log/".to_string()],
+            ..Default::default()
+        };
+        for spec in REGISTRY {
+            let src = spec.build(&opts).unwrap();
+            assert_eq!(src.name(), spec.id);
+            assert_eq!(src.is_passive(), spec.passive);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// src/sources/tls_san.rs
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use colored::Colorize;
+use url::Url;
+
+use crate::dns::{self, Resolver};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::{Evidence, SubdomainMap};
+use crate::tls;
+
+/// Ports probed when none are configured.
+pub const DEFAULT_PORTS: &[u16] = &[443];
+
+/// Connect and handshake timeout per host and port.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Names learned from certificates are probed in turn, this many times over.
+const MAX_ROUNDS: usize = 3;
+
+/// Reads the certificates of live hosts and records the in-scope names they
+/// were issued for (common name and Subject Alternative Names).
+///
+/// Runs after the other sources and probes every host they found
+/// (`DiscoveryConfig::seed_hosts`) plus the target itself. Hosts are resolved
+/// through the configured resolvers; those that don't resolve are skipped.
+pub struct TlsSan {
+    ports: Vec<u16>,
+}
+
+impl TlsSan {
+    pub fn new(ports: Vec<u16>) -> Self {
+        let ports = if ports.is_empty() {
+            DEFAULT_PORTS.to_vec()
+        } else {
+            ports
+        };
+        Self { ports }
+    }
+}
+
+impl SubdomainSource for TlsSan {
+    fn name(&self) -> &'static str {
+        "tls-san"
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        let mut probed: HashSet<String> = HashSet::new();
+        let mut pending: Vec<String> = cfg
+            .start_url
+            .host_str()
+            .into_iter()
+            .map(str::to_lowercase)
+            .collect();
+        pending.extend(cfg.seed_hosts.iter().cloned());
+
+        let map = Mutex::new(SubdomainMap::new());
+        let resolver = Resolver::new(cfg.resolvers.clone(), dns::DEFAULT_TIMEOUT);
+
+        for _ in 0..MAX_ROUNDS {
+            let mut hosts: Vec<String> = Vec::new();
+            let mut proxied = 0;
+            for host in pending.drain(..) {
+                if !probed.insert(host.clone()) {
+                    continue;
+                }
+                // Raw TLS connections cannot follow an upstream HTTP proxy
+                if cfg.http.proxy().upstream_for(&host).is_some() {
+                    proxied += 1;
+                    continue;
+                }
+                hosts.push(host);
+            }
+            if proxied > 0 {
+                ctx.warn(format!(
+                    "skipped {} host(s) that would bypass the proxy; add them to --no-proxy \
+                     or use --source-proxy tls-san=direct",
+                    proxied
+                ));
+            }
+            if hosts.is_empty() || ctx.is_cancelled() {
+                break;
+            }
+
+            dns::ensure_unproxied(&cfg.resolvers, cfg.http.proxy(), self.name())?;
+
+            eprintln!(
+                "[*] Reading TLS certificates of {} host(s) on port(s) {:?}",
+                hosts.len(),
+                self.ports
+            );
+
+            let found = Mutex::new(Vec::new());
+            let next = AtomicUsize::new(0);
+            let unresolved = AtomicUsize::new(0);
+            let lookup_errors = Mutex::new(Vec::new());
+
+            thread::scope(|scope| {
+                for _ in 0..cfg.workers.max(1) {
+                    scope.spawn(|| {
+                        while !ctx.is_cancelled() {
+                            let Some(host) = hosts.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                                break;
+                            };
+
+                            // Resolved like the DNS sources do; passive and archived
+                            // hosts are often gone, which is no reason to warn
+                            let addrs = match resolver.resolve(host) {
+                                Ok(Some(lookup)) if !lookup.addrs.is_empty() => lookup.addrs,
+                                Ok(_) => {
+                                    unresolved.fetch_add(1, Ordering::Relaxed);
+                                    continue;
+                                }
+                                Err(e) => {
+                                    lookup_errors.lock().unwrap().push(e);
+                                    continue;
+                                }
+                            };
+
+                            self.probe(host, &addrs, cfg, ctx, &map, &found);
+                        }
+                    });
+                }
+            });
+
+            let unresolved = unresolved.into_inner();
+            if unresolved > 0 {
+                eprintln!(
+                    "[~] {} host(s) did not resolve and were not probed",
+                    unresolved
+                );
+            }
+            let lookup_errors = lookup_errors.into_inner().unwrap();
+            if let Some(first) = lookup_errors.first() {
+                ctx.warn(format!(
+                    "{} host(s) could not be looked up and were not probed (first error: {:#})",
+                    lookup_errors.len(),
+                    first
+                ));
+            }
+
+            pending = found.into_inner().unwrap();
+        }
+
+        Ok(map.into_inner().unwrap())
+    }
+}
+
+impl TlsSan {
+    /// Read the certificate `host` presents on every configured port and
+    /// record the in-scope names in `map`, also collecting them in `found`.
+    fn probe(
+        &self,
+        host: &str,
+        addrs: &[IpAddr],
+        cfg: &DiscoveryConfig,
+        ctx: &SourceContext,
+        map: &Mutex<SubdomainMap>,
+        found: &Mutex<Vec<String>>,
+    ) {
+        for &port in &self.ports {
+            let names = match tls::fetch_certificate_names(host, addrs, port, PROBE_TIMEOUT) {
+                Ok(names) => names,
+                Err(e) => {
+                    ctx.warn(format!("{}:{}: {:#}", host, port, e));
+                    continue;
+                }
+            };
+
+            let mut map = map.lock().unwrap();
+            for (name, field) in names.in_scope(&cfg.root_domain) {
+                let Ok(url) = Url::parse(&format!("https://{}/", name)) else {
+                    continue;
+                };
+                if map.add_url(&url, &cfg.root_domain, self.name()) {
+                    eprintln!(
+                        "{} Discovered (sub)domain {} in the certificate of {}:{}",
+                        "[+]".green().bold(),
+                        name.bold(),
+                        host,
+                        port
+                    );
+                }
+                map.record_evidence(
+                    &name,
+                    Evidence {
+                        via: format!("tls-{}", field),
+                        found_on: format!("{}:{}", host, port),
+                    },
+                );
+                found.lock().unwrap().push(name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::http::{HttpClient, HttpSettings};
+    use crate::proxy::{BypassList, ProxyMode};
+    use crate::tls::test_server;
+
+    #[test]
+    fn test_records_in_scope_certificate_names() {
+        let port = test_server::spawn(
+            "Portal.localhost",
+            &["localhost", "www.localhost", "*.dev.localhost", "other.org"],
+        );
+        let resolver = dns::stub_server::spawn(HashMap::from([
+            ("localhost".to_string(), Ipv4Addr::LOCALHOST),
+            ("*.localhost".to_string(), Ipv4Addr::LOCALHOST),
+        ]));
+
+        // A seed that no longer resolves is skipped without a warning
+        let cfg = DiscoveryConfig {
+            start_url: Url::parse("https://localhost/").unwrap(),
+            root_domain: "localhost".to_string(),
+            resolvers: vec![resolver],
+            seed_hosts: vec!["old.dev.localhost".to_string()],
+            ..DiscoveryConfig::for_test("https://www.example.com")
+        };
+
+        let ctx = SourceContext::new();
+        let map = TlsSan::new(vec![port]).discover(&cfg, &ctx).unwrap();
+        assert_eq!(
+            map.hosts(),
+            vec!["localhost", "portal.localhost", "www.localhost"]
+        );
+        assert!(ctx.take_warnings().is_empty());
+
+        let evidence = &map.get("portal.localhost").unwrap().evidence;
+        assert!(evidence.contains(&Evidence {
+            via: "tls-cn".to_string(),
+            found_on: format!("localhost:{}", port),
+        }));
+    }
+
+    #[test]
+    fn test_skips_hosts_behind_env_proxy() {
+        let port = test_server::spawn("localhost", &["www.localhost"]);
+
+        // As if HTTPS_PROXY were set and --proxy not given
+        let env = |name: &str| (name == "HTTPS_PROXY").then(|| "127.0.0.1:9".to_string());
+        let proxy = ProxyMode::from_vars(env, &BypassList::default()).unwrap();
+        let cfg = DiscoveryConfig {
+            start_url: Url::parse("https://localhost/").unwrap(),
+            root_domain: "localhost".to_string(),
+            http: HttpClient::new(&HttpSettings {
+                proxy,
+                ..Default::default()
+            })
+            .unwrap(),
+            ..DiscoveryConfig::for_test("https://www.example.com")
+        };
+
+        let ctx = SourceContext::new();
+        let map = TlsSan::new(vec![port]).discover(&cfg, &ctx).unwrap();
+        assert!(map.hosts().is_empty());
+        assert!(
+            ctx.take_warnings()
+                .iter()
+                .any(|w| w.contains("skipped 1 host(s)"))
+        );
+    }
+}
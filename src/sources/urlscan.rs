@@ -0,0 +1,180 @@
+// src/sources/urlscan.rs
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::sources::api::{self, ApiEndpoint};
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+pub const DEFAULT_BASE_URL: &str = "https://urlscan.io/";
+
+/// Results requested per page.
+const PAGE_SIZE: usize = 100;
+
+/// Pages fetched at most; a warning says so when results are cut off there.
+const MAX_PAGES: usize = 50;
+
+/// Domains of pages scanned by urlscan.io (or a compatible search API).
+pub struct UrlScan {
+    endpoint: ApiEndpoint,
+}
+
+impl UrlScan {
+    pub fn new(endpoint: ApiEndpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+/// A page of `/api/v1/search/`.
+#[derive(Deserialize, Debug)]
+struct SearchPage {
+    #[serde(default)]
+    results: Vec<SearchResult>,
+    #[serde(default)]
+    has_more: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchResult {
+    page: Option<Target>,
+    task: Option<Target>,
+
+    /// Cursor for the next page when this is the last result
+    #[serde(default)]
+    sort: Vec<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Target {
+    domain: Option<String>,
+}
+
+impl SubdomainSource for UrlScan {
+    fn name(&self) -> &'static str {
+        "urlscan"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
+    }
+
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+        eprintln!("[*] Querying urlscan.io for domain {}", cfg.root_domain);
+
+        let mut map = SubdomainMap::new();
+        let mut search_after: Option<String> = None;
+
+        for page_no in 1..=MAX_PAGES {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            let mut url = self.endpoint.url("api/v1/search/")?;
+            url.query_pairs_mut()
+                .append_pair("q", &format!("domain:{}", cfg.root_domain))
+                .append_pair("size", &PAGE_SIZE.to_string());
+            if let Some(cursor) = &search_after {
+                url.query_pairs_mut().append_pair("search_after", cursor);
+            }
+
+            let page: SearchPage = api::get(
+                cfg,
+                ctx,
+                "urlscan query",
+                || {
+                    let req = cfg.http.get(url.as_str());
+                    match &self.endpoint.api_key {
+                        Some(key) => req.header("API-Key", key),
+                        None => req,
+                    }
+                },
+                |body| {
+                    serde_json::from_slice(body)
+                        .context("Failed to parse JSON response from urlscan")
+                },
+            )?;
+
+            for result in &page.results {
+                for target in [&result.page, &result.task].into_iter().flatten() {
+                    if let Some(domain) = &target.domain {
+                        api::record_host(&mut map, domain, cfg, self.name());
+                    }
+                }
+            }
+
+            // "1700000000000,abc-123": the sort values of the last result
+            let cursor = page.results.last().map(|r| {
+                r.sort
+                    .iter()
+                    .map(|v| match v {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            });
+            match cursor {
+                Some(cursor) if page.has_more && !cursor.is_empty() && page_no == MAX_PAGES => {
+                    ctx.warn(format!(
+                        "urlscan results cut off after {} pages (search_after {})",
+                        MAX_PAGES, cursor
+                    ));
+                }
+                Some(cursor) if page.has_more && !cursor.is_empty() => search_after = Some(cursor),
+                _ => break,
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::http::stub_server::{self, response};
+
+    #[test]
+    fn test_follows_search_after_cursor() {
+        let port = stub_server::spawn(|req, _| {
+            if req.header("api-key") != Some("k") {
+                return response("401 Unauthorized", &[], "");
+            }
+            let body = if req.path.contains("search_after=1700000000000%2Cabc") {
+                json!({
+                    "results": [{"page": {"domain": "shop.example.com"}, "sort": [1, "z"]}],
+                    "has_more": false
+                })
+            } else {
+                json!({
+                    "results": [
+                        {"page": {"domain": "www.example.com"}, "task": {"domain": "example.com"}, "sort": [1800000000000u64, "x"]},
+                        {"page": {"domain": "cdn.other.net"}, "sort": [1700000000000u64, "abc"]}
+                    ],
+                    "has_more": true
+                })
+            };
+            response("200 OK", &[], &body.to_string())
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            Some("k".to_string()),
+        )
+        .unwrap();
+
+        let map = UrlScan::new(endpoint)
+            .discover(&cfg, &SourceContext::new())
+            .unwrap();
+        assert_eq!(
+            map.hosts(),
+            vec!["example.com", "shop.example.com", "www.example.com"]
+        );
+    }
+}
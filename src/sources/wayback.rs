@@ -1,114 +1,329 @@
 // src/sources/wayback.rs
 
 use std::collections::HashSet;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use colored::Colorize;
-use serde_json::Value;
+use serde::Deserializer as _;
+use serde::de::{SeqAccess, Visitor};
 use url::Url;
 
-use crate::sources::{DiscoveryConfig, SubdomainSource};
-use crate::subdomains::SubdomainMap;
+use crate::http::HttpStatusError;
+use crate::sources::api::ApiEndpoint;
+use crate::sources::{DiscoveryConfig, SourceContext, SubdomainSource};
+use crate::subdomains::{ArchivedUrl, SubdomainMap};
 
-pub struct WaybackArchive;
+pub const DEFAULT_BASE_URL: &str = "https://web.archive.org/";
+
+/// The CDX API is slow for large domains, so a shorter `--timeout` is raised
+/// to this for its queries.
+const MIN_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Rows requested per CDX page.
+const PAGE_SIZE: usize = 10_000;
+
+/// Pages fetched at most; a warning says so when results are cut off there.
+const MAX_PAGES: usize = 100;
+
+/// Historical URLs from the Wayback Machine.
+///
+/// By default only the first URL seen per host is recorded. With `all_paths`
+/// every archived URL is kept, along with its capture time and status, so the
+/// map doubles as an inventory of historical endpoints.
+pub struct WaybackArchive {
+    endpoint: ApiEndpoint,
+    all_paths: bool,
+}
 
 impl WaybackArchive {
-    pub fn new() -> Self {
-        Self
+    pub fn new(endpoint: ApiEndpoint, all_paths: bool) -> Self {
+        Self {
+            endpoint,
+            all_paths,
+        }
     }
 }
 
 impl SubdomainSource for WaybackArchive {
     fn name(&self) -> &'static str {
-        "Wayback Machine"
+        "wayback"
+    }
+
+    fn is_passive(&self) -> bool {
+        true
     }
 
-    fn discover(&self, cfg: &DiscoveryConfig) -> Result<SubdomainMap> {
+    fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
         eprintln!("[*] Querying Wayback Machine API...");
 
-        let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(60))
-            .user_agent("subrapid-knightchaser/0.1")
-            .build()
-            .context("Failed to build HTTP client for Wayback Machine")?;
+        let mut map = SubdomainMap::new();
+        let mut processed_hosts = HashSet::new();
+        let mut resume_key: Option<String> = None;
+        let mut rows = 0;
+        let fields = if self.all_paths {
+            "original,timestamp,statuscode"
+        } else {
+            "original"
+        };
 
-        let query_url = format!(
-            "http://web.archive.org/cdx/search/cdx?url={}&output=json&limit=100000&fl=original",
-            cfg.root_domain
-        );
+        for page_no in 1..=MAX_PAGES {
+            if ctx.is_cancelled() {
+                break;
+            }
+
+            // matchType=domain covers every subdomain, collapse=urlkey drops
+            // repeated captures of the same URL
+            let mut query_url = self.endpoint.url("cdx/search/cdx")?;
+            query_url
+                .query_pairs_mut()
+                .append_pair("url", &cfg.root_domain)
+                .append_pair("matchType", "domain")
+                .append_pair("collapse", "urlkey")
+                .append_pair("output", "json")
+                .append_pair("fl", fields)
+                .append_pair("limit", &PAGE_SIZE.to_string())
+                .append_pair("showResumeKey", "true");
+            if let Some(key) = &resume_key {
+                query_url.query_pairs_mut().append_pair("resumeKey", key);
+            }
+
+            let (next_key, page_rows) = cfg.retry.run(ctx, "Wayback Machine query", || {
+                let resp = cfg
+                    .http
+                    .get(query_url.as_str())
+                    .timeout(cfg.http.timeout().max(MIN_QUERY_TIMEOUT))
+                    .send()
+                    .with_context(|| {
+                        format!(
+                            "Failed to send request to Wayback Machine for domain {}",
+                            cfg.root_domain
+                        )
+                    })?;
 
-        let resp = client.get(&query_url).send().with_context(|| {
-            format!(
-                "Failed to send request to Wayback Machine for domain {}",
-                cfg.root_domain
-            )
-        })?;
+                if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok((None, 0));
+                }
+                if !resp.status().is_success() {
+                    return Err(anyhow::Error::from(HttpStatusError::from_response(&resp))
+                        .context("Wayback Machine returned non-success status code"));
+                }
 
-        if !resp.status().is_success() {
-            if resp.status() == reqwest::StatusCode::NOT_FOUND {
-                return Ok(SubdomainMap::new());
+                // Rows are handled as they arrive; a retried page just finds
+                // the hosts it already added, and is only counted once
+                let mut page_rows = 0;
+                let next_key = stream_rows(resp, |row| {
+                    page_rows += 1;
+                    self.process_row(row, cfg, &mut map, &mut processed_hosts);
+                })?;
+                Ok((next_key, page_rows))
+            })?;
+            rows += page_rows;
+
+            match next_key {
+                Some(key) if page_no == MAX_PAGES => {
+                    ctx.warn(format!(
+                        "Wayback results cut off after {} pages (resume key {})",
+                        MAX_PAGES, key
+                    ));
+                }
+                Some(key) => resume_key = Some(key),
+                None => break,
             }
-            anyhow::bail!(
-                "Wayback Machine returned non-success status code: {}",
-                resp.status()
+        }
+
+        eprintln!("[~] Processed {} historical records", rows);
+        Ok(map)
+    }
+}
+
+impl WaybackArchive {
+    /// Handle one `[original, timestamp, statuscode]` row (just `[original]`
+    /// unless all paths are kept).
+    fn process_row(
+        &self,
+        row: Vec<String>,
+        cfg: &DiscoveryConfig,
+        map: &mut SubdomainMap,
+        processed_hosts: &mut HashSet<String>,
+    ) {
+        let mut fields = row.into_iter();
+        let Some(url_str) = fields.next() else {
+            return;
+        };
+        let Ok(parsed_url) = Url::parse(&url_str) else {
+            return;
+        };
+        let Some(host_str) = parsed_url.host_str() else {
+            return;
+        };
+        let host_clean = host_str.trim().to_lowercase();
+
+        // If we've seen this host already, skip it immediately,
+        // unless every path is wanted.
+        if !processed_hosts.insert(host_clean.clone()) && !self.all_paths {
+            return;
+        }
+
+        if map.add_url(&parsed_url, &cfg.root_domain, self.name()) {
+            eprintln!(
+                "{} Discovered potential (sub)domain {} via Wayback Machine",
+                "[+]".green().bold(),
+                host_clean.bold()
             );
         }
 
-        let text = resp
-            .text()
-            .context("Failed to read Wayback response body")?;
-        let root: Value = serde_json::from_str(&text).context("Failed to parse Wayback JSON")?;
-        let rows = root
-            .as_array()
-            .context("Wayback response was not a JSON array")?;
+        if self.all_paths {
+            map.record_archived(
+                &host_clean,
+                ArchivedUrl {
+                    url: url_str,
+                    timestamp: fields.next().unwrap_or_default(),
+                    status: fields.next().unwrap_or_default(),
+                },
+            );
+        }
+    }
+}
 
-        let mut map = SubdomainMap::new();
-        let mut processed_hosts = HashSet::new();
+/// Parse a CDX JSON response row by row without holding it in memory,
+/// calling `on_row` for every data row, and return the resume key if the
+/// response has one.
+///
+/// ```json
+/// [["original"],
+///  ["http://www.example.com:80/"],
+///  ...,
+///  [],
+///  ["com,example)/page+20240101000000"]]
+/// ```
+///
+/// The first row is the header; the resume key follows an empty row.
+/// An empty body means no captures.
+fn stream_rows(reader: impl Read, on_row: impl FnMut(Vec<String>)) -> Result<Option<String>> {
+    let mut reader = BufReader::new(reader);
+    if reader
+        .fill_buf()
+        .context("Failed to read Wayback response body")?
+        .is_empty()
+    {
+        return Ok(None);
+    }
 
-        // NOTE:
-        // -1 because first row is header. Example output be like:
-        // ```json
-        // [["original"],
-        // ["http://www.youtube.com:80/"],
-        // ["http://www.youtube.com:80/"],
-        // ["http://www.youtube.com:80/"],
-        // ...
-        // ```
-        eprintln!("[~] Processing {} historical records...", rows.len() - 1);
-
-        // NOTE:
-        // Skip the header row
-        for row in rows.iter().skip(1) {
-            if let Some(url_str) = row.get(0).and_then(|v| v.as_str()) {
-                // Parse URL
-                let Ok(parsed_url) = Url::parse(url_str) else {
-                    continue;
-                };
-
-                let Some(host_str) = parsed_url.host_str() else {
-                    continue;
-                };
-
-                let host_clean = host_str.trim().to_lowercase();
-
-                // 1. Deduplication Filter (HashSet)
-                // If we've seen this host in this loop, skip it immediately.
-                if !processed_hosts.insert(host_clean.clone()) {
-                    continue;
-                }
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    de.deserialize_seq(CdxRows { on_row })
+        .context("Failed to parse Wayback JSON")
+}
 
-                // 2. Add to map
-                if map.add_url(&parsed_url, &cfg.root_domain) {
-                    eprintln!(
-                        "{} Discovered potential (sub)domain {} via Wayback Machine",
-                        "[+]".green().bold(),
-                        format!("{}", host_clean).bold()
-                    );
-                }
+struct CdxRows<F> {
+    on_row: F,
+}
+
+impl<'de, F: FnMut(Vec<String>)> Visitor<'de> for CdxRows<F> {
+    type Value = Option<String>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON array of CDX rows")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut header = true;
+        let mut after_blank = false;
+        let mut resume_key = None;
+
+        while let Some(row) = seq.next_element::<Vec<String>>()? {
+            if header {
+                header = false;
+            } else if row.is_empty() {
+                after_blank = true;
+            } else if after_blank {
+                resume_key = row.into_iter().next();
+            } else {
+                (self.on_row)(row);
             }
         }
 
-        Ok(map)
+        Ok(resume_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::stub_server::{self, response};
+
+    #[test]
+    fn test_walks_every_page_with_resume_key() {
+        let port = stub_server::spawn(|req, _| {
+            let path = req.path.as_str();
+            if !path.contains("matchType=domain") || !path.contains("collapse=urlkey") {
+                return response("400 Bad Request", &[], "");
+            }
+            let body = if path.contains("resumeKey=com%2Cexample%2Cshop") {
+                r#"[["original"],["https://shop.example.com/cart"]]"#
+            } else {
+                r#"[["original"],
+                    ["http://www.example.com:80/"],
+                    ["https://www.example.com/about"],
+                    ["https://evil.org/"],
+                    [],
+                    ["com,example,shop)/ 20240101000000"]]"#
+            };
+            response("200 OK", &[], body)
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            None,
+        )
+        .unwrap();
+
+        let ctx = SourceContext::new();
+        let map = WaybackArchive::new(endpoint, false)
+            .discover(&cfg, &ctx)
+            .unwrap();
+        assert_eq!(map.hosts(), vec!["shop.example.com", "www.example.com"]);
+        assert_eq!(ctx.attempts(), 2);
+    }
+
+    #[test]
+    fn test_all_paths_keeps_every_capture() {
+        let port = stub_server::spawn(|req, _| {
+            if !req.path.contains("fl=original%2Ctimestamp%2Cstatuscode") {
+                return response("400 Bad Request", &[], "");
+            }
+            let body = r#"[["original","timestamp","statuscode"],
+                ["http://www.example.com:80/","20150101000000","200"],
+                ["https://www.example.com/admin?x=1","20180305120000","403"],
+                ["https://www.example.com/old","20120101000000","-"]]"#;
+            response("200 OK", &[], body)
+        });
+
+        let cfg = DiscoveryConfig::for_test("https://www.example.com");
+        let endpoint = ApiEndpoint::new(
+            DEFAULT_BASE_URL,
+            Some(&format!("http://127.0.0.1:{}", port)),
+            None,
+        )
+        .unwrap();
+
+        let map = WaybackArchive::new(endpoint, true)
+            .discover(&cfg, &SourceContext::new())
+            .unwrap();
+        let entry = map.get("www.example.com").unwrap();
+        assert_eq!(
+            entry.paths.iter().collect::<Vec<_>>(),
+            vec!["/", "/admin", "/old"]
+        );
+        assert_eq!(entry.archived.len(), 3);
+        assert!(entry.archived.contains(&ArchivedUrl {
+            url: "https://www.example.com/admin?x=1".to_string(),
+            timestamp: "20180305120000".to_string(),
+            status: "403".to_string(),
+        }));
     }
 }
@@ -1,16 +1,79 @@
 // src/subdomains.rs
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use colored::Colorize;
 use psl::domain_str;
+use serde::Serialize;
 use url::Url;
 
+/// Upper bound on evidence kept per host, so popular hosts don't grow without limit.
+const MAX_EVIDENCE_PER_HOST: usize = 32;
+
+/// Same idea for redirect chains.
+const MAX_REDIRECT_CHAINS_PER_HOST: usize = 8;
+
+/// Where a reference to a host was found.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Evidence {
+    /// How the reference was found, e.g. "script[src]" or "csp"
+    pub via: String,
+
+    /// The page or file that contained it
+    pub found_on: String,
+}
+
+/// A capture of a URL in a web archive.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ArchivedUrl {
+    /// The URL as originally captured
+    pub url: String,
+
+    /// When it was captured, as a 14-digit `YYYYMMDDhhmmss` timestamp
+    pub timestamp: String,
+
+    /// Status code of the archived response ("-" when the archive has none)
+    pub status: String,
+}
+
+/// Everything we know about a single discovered host.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubdomainEntry {
+    /// Paths seen on this host (query and fragment stripped)
+    pub paths: BTreeSet<String>,
+
+    /// Names of the sources that reported this host (see `SubdomainSource::name`)
+    pub sources: BTreeSet<String>,
+
+    /// The first URL that evidenced this host
+    pub first_seen: String,
+
+    /// When the host was first discovered, as seconds since the Unix epoch
+    pub discovered_at: u64,
+
+    /// Addresses the host resolved to (only filled by resolver-based sources)
+    pub answers: BTreeSet<IpAddr>,
+
+    /// Where references to the host were found (filled by the crawler and tls-san)
+    pub evidence: BTreeSet<Evidence>,
+
+    /// Redirect chains (requested URL first, final URL last) this host took
+    /// part in (only filled by the crawler)
+    pub redirect_chains: BTreeSet<Vec<String>>,
+
+    /// Archived URLs on this host (only filled by wayback with
+    /// `--wayback-all-paths`)
+    pub archived: BTreeSet<ArchivedUrl>,
+}
+
 /// Holds subdomains and their paths
 #[derive(Debug, Default, Clone)]
 pub struct SubdomainMap {
-    // host -> set of paths
-    inner: HashMap<String, HashSet<String>>,
+    // host -> entry
+    inner: HashMap<String, SubdomainEntry>,
 }
 
 impl SubdomainMap {
@@ -20,18 +83,16 @@ impl SubdomainMap {
         }
     }
 
-    /// Add a URL if it belongs to the given root domain.
-    pub fn add_url(&mut self, url: &Url, root_domain: &str) -> bool {
+    /// Add a URL reported by `source` if it belongs to the given root domain.
+    /// Returns true if the host was not in the map before.
+    pub fn add_url(&mut self, url: &Url, root_domain: &str, source: &str) -> bool {
         // host must exist
         let host = match url.host_str() {
             Some(h) => h.to_lowercase(),
             None => return false,
         };
 
-        // Check if host belongs to root_domain
-        // Either exact match, or ends with ".root_domain"
-        let suffix = format!(".{}", root_domain);
-        if host != root_domain && !host.ends_with(&suffix) {
+        if !host_in_scope(&host, root_domain) {
             return false;
         }
 
@@ -40,29 +101,137 @@ impl SubdomainMap {
         normalized_url.set_query(None);
         normalized_url.set_fragment(None);
 
-        let host = normalized_url.host_str().unwrap().to_string();
         let path = normalized_url.path().to_string();
 
         // Insert into map
-        let entry = self.inner.entry(host).or_insert_with(HashSet::new);
-        let is_new_host = entry.is_empty(); // If it was empty, this is the first path.
-        entry.insert(path);
+        let is_new_host = !self.inner.contains_key(&host);
+        let entry = self.inner.entry(host).or_insert_with(|| SubdomainEntry {
+            paths: BTreeSet::new(),
+            sources: BTreeSet::new(),
+            first_seen: normalized_url.to_string(),
+            discovered_at: unix_now(),
+            answers: BTreeSet::new(),
+            evidence: BTreeSet::new(),
+            redirect_chains: BTreeSet::new(),
+            archived: BTreeSet::new(),
+        });
+        entry.paths.insert(path);
+        entry.sources.insert(source.to_string());
 
         is_new_host
     }
 
+    /// Remember which addresses a host resolved to.
+    /// The host must already be in the map.
+    pub fn record_answers(&mut self, host: &str, addrs: &[IpAddr]) {
+        if let Some(entry) = self.inner.get_mut(&host.to_lowercase()) {
+            entry.answers.extend(addrs.iter().copied());
+        }
+    }
+
+    /// Withdraw the DNS answers and the `source` attribution of a host.
+    /// The host is dropped altogether if no other source reported it.
+    pub fn retract_answers(&mut self, host: &str, source: &str) {
+        let host = host.to_lowercase();
+        let Some(entry) = self.inner.get_mut(&host) else {
+            return;
+        };
+
+        entry.answers.clear();
+        entry.sources.remove(source);
+        if entry.sources.is_empty() {
+            self.inner.remove(&host);
+        }
+    }
+
+    /// Remember where a reference to a host was found.
+    /// The host must already be in the map.
+    pub fn record_evidence(&mut self, host: &str, evidence: Evidence) {
+        if let Some(entry) = self.inner.get_mut(&host.to_lowercase()) {
+            add_evidence(&mut entry.evidence, [evidence]);
+        }
+    }
+
+    /// Remember an archived capture of a URL on a host.
+    /// The host must already be in the map.
+    pub fn record_archived(&mut self, host: &str, capture: ArchivedUrl) {
+        if let Some(entry) = self.inner.get_mut(&host.to_lowercase()) {
+            entry.archived.insert(capture);
+        }
+    }
+
+    /// Attach a redirect chain to every known host that appears in it.
+    pub fn record_redirect_chain(&mut self, chain: &[Url]) {
+        let chain_str: Vec<String> = chain.iter().map(Url::to_string).collect();
+
+        let hosts: BTreeSet<String> = chain
+            .iter()
+            .filter_map(|u| u.host_str())
+            .map(str::to_lowercase)
+            .collect();
+        for host in hosts {
+            if let Some(entry) = self.inner.get_mut(&host)
+                && entry.redirect_chains.len() < MAX_REDIRECT_CHAINS_PER_HOST
+            {
+                entry.redirect_chains.insert(chain_str.clone());
+            }
+        }
+    }
+
+    /// Hosts with DNS answers attached, sorted by host.
+    pub fn resolved_hosts(&self) -> Vec<(&str, &BTreeSet<IpAddr>)> {
+        let mut out: Vec<_> = self
+            .inner
+            .iter()
+            .filter(|(_, entry)| !entry.answers.is_empty())
+            .map(|(host, entry)| (host.as_str(), &entry.answers))
+            .collect();
+        out.sort();
+        out
+    }
+
+    /// Look up the entry for a host.
+    #[cfg(test)]
+    pub fn get(&self, host: &str) -> Option<&SubdomainEntry> {
+        self.inner.get(host)
+    }
+
+    /// All entries, sorted by host.
+    pub fn entries(&self) -> Vec<(&str, &SubdomainEntry)> {
+        let mut out: Vec<_> = self
+            .inner
+            .iter()
+            .map(|(host, entry)| (host.as_str(), entry))
+            .collect();
+        out.sort_by_key(|(host, _)| *host);
+        out
+    }
+
+    /// All hosts in the map, sorted.
+    pub fn hosts(&self) -> Vec<&str> {
+        let mut hosts: Vec<&str> = self.inner.keys().map(String::as_str).collect();
+        hosts.sort();
+        hosts
+    }
+
     /// Pretty-print everything in the map
     #[allow(dead_code)]
     pub fn print(&self) {
-        for (host, paths) in &self.inner {
-            println!("{host}");
-            for path in paths {
+        for host in self.hosts() {
+            let entry = &self.inner[host];
+            let sources: Vec<&str> = entry.sources.iter().map(String::as_str).collect();
+            println!(
+                "{host} [{}] (first seen at {})",
+                sources.join(", "),
+                entry.first_seen
+            );
+            for path in &entry.paths {
                 println!("  {path}");
             }
         }
     }
 
-    /// Print only subdomains (host part before the root domain),
+    /// Write only subdomains (host part before the root domain),
     /// with the subdomain highlighted and root domain kept normal.
     ///
     /// Example:
@@ -70,13 +239,15 @@ impl SubdomainMap {
     ///   prints: "<cyan bold>mail</cyan bold>.stack.com"
     ///
     ///   host: "stack.com" (no subdomain) -> skipped.
-    pub fn print_subdomains_only(&self, root_domain: &str) {
-        let mut hosts: Vec<_> = self.inner.keys().collect();
-        hosts.sort();
-
-        for host in hosts {
-            let host = host.as_str();
-
+    ///
+    /// Highlighting is skipped entirely when `color` is false.
+    pub fn write_subdomains_only(
+        &self,
+        out: &mut dyn Write,
+        root_domain: &str,
+        color: bool,
+    ) -> io::Result<()> {
+        for host in self.hosts() {
             let Some(stripped) = host.strip_suffix(root_domain) else {
                 // should not happen, as we only store same-root-domain hosts
                 continue;
@@ -88,22 +259,78 @@ impl SubdomainMap {
                 continue;
             }
 
-            let sub = stripped.cyan().bold();
-            println!("{sub}.{root_domain}");
+            if color {
+                writeln!(out, "{}.{root_domain}", stripped.cyan().bold())?;
+            } else {
+                writeln!(out, "{stripped}.{root_domain}")?;
+            }
         }
+
+        Ok(())
     }
 
     /// Merge another SubdomainMap into this one.
+    /// Sources, paths and answers are unioned; the earliest discovery wins.
     pub fn merge_from(&mut self, other: SubdomainMap) {
-        for (host, paths) in other.inner {
-            self.inner
-                .entry(host)
-                .or_insert_with(HashSet::new)
-                .extend(paths);
+        for (host, theirs) in other.inner {
+            let Some(ours) = self.inner.get_mut(&host) else {
+                self.inner.insert(host, theirs);
+                continue;
+            };
+
+            if theirs.discovered_at < ours.discovered_at {
+                ours.discovered_at = theirs.discovered_at;
+                ours.first_seen = theirs.first_seen;
+            }
+            ours.paths.extend(theirs.paths);
+            ours.sources.extend(theirs.sources);
+            ours.answers.extend(theirs.answers);
+            add_evidence(&mut ours.evidence, theirs.evidence);
+            for chain in theirs.redirect_chains {
+                if ours.redirect_chains.len() >= MAX_REDIRECT_CHAINS_PER_HOST {
+                    break;
+                }
+                ours.redirect_chains.insert(chain);
+            }
+            ours.archived.extend(theirs.archived);
         }
     }
 }
 
+fn add_evidence(set: &mut BTreeSet<Evidence>, evidence: impl IntoIterator<Item = Evidence>) {
+    for e in evidence {
+        if set.len() >= MAX_EVIDENCE_PER_HOST {
+            break;
+        }
+        set.insert(e);
+    }
+}
+
+/// Current time as seconds since the Unix epoch.
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Check if given host is in scope of the root_domain
+/// Example:
+///     root_domain: `example.com`
+///     in scope: `example.com`, `sub.example.com`
+///     out of scope: `other.com`, `example.org`, `sub.example.org`
+pub fn host_in_scope(host: &str, root_domain: &str) -> bool {
+    let host = host.to_lowercase();
+    let root = root_domain.to_lowercase();
+
+    if host == root {
+        return true;
+    }
+
+    let suffix = format!(".{}", root);
+    host.ends_with(&suffix)
+}
+
 /// Extract the registrable ("root") domain using the Public Suffix List.
 ///
 /// Examples:
@@ -116,7 +343,62 @@ pub fn extract_root_domain(host: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::extract_root_domain;
+    use url::Url;
+
+    use super::{SubdomainMap, extract_root_domain};
+
+    #[test]
+    fn test_merge_keeps_provenance() {
+        let url = Url::parse("https://api.example.com/v1?q=1").unwrap();
+
+        let mut crawled = SubdomainMap::new();
+        assert!(crawled.add_url(&url, "example.com", "html-crawler"));
+        assert!(!crawled.add_url(&url, "example.com", "html-crawler"));
+
+        let mut archived = SubdomainMap::new();
+        archived.add_url(&url.join("/old").unwrap(), "example.com", "wayback");
+        assert!(!archived.add_url(
+            &Url::parse("https://other.org").unwrap(),
+            "example.com",
+            "crtsh"
+        ));
+
+        crawled.merge_from(archived);
+
+        let entry = crawled.get("api.example.com").unwrap();
+        assert_eq!(entry.first_seen, "https://api.example.com/v1");
+        assert_eq!(
+            entry.sources.iter().collect::<Vec<_>>(),
+            vec!["html-crawler", "wayback"]
+        );
+        assert_eq!(entry.paths.iter().collect::<Vec<_>>(), vec!["/old", "/v1"]);
+    }
+
+    #[test]
+    fn test_redirect_chain_recorded_on_every_hop() {
+        let chain: Vec<Url> = [
+            "https://login.example.com/",
+            "https://sso.example.com/auth",
+            "https://idp.other.org/",
+            "https://app.example.com/home",
+        ]
+        .iter()
+        .map(|u| Url::parse(u).unwrap())
+        .collect();
+
+        let mut map = SubdomainMap::new();
+        for url in &chain {
+            map.add_url(url, "example.com", "html-crawler");
+        }
+        map.record_redirect_chain(&chain);
+
+        for host in ["login.example.com", "sso.example.com", "app.example.com"] {
+            let chains = &map.get(host).unwrap().redirect_chains;
+            assert_eq!(chains.len(), 1);
+            assert_eq!(chains.first().unwrap().len(), 4);
+        }
+        assert!(map.get("idp.other.org").is_none());
+    }
 
     #[test]
     fn test_extract_root_domain_basic() {
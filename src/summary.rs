@@ -0,0 +1,256 @@
+// src/summary.rs
+
+use std::panic::{self, AssertUnwindSafe};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::sources::{DiscoveryConfig, Quota, SourceContext, SubdomainSource};
+use crate::subdomains::SubdomainMap;
+
+/// How many warnings to show per partial source in the summary.
+const MAX_REASONS_SHOWN: usize = 3;
+
+/// How a single source's run ended.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SourceStatus {
+    /// Finished without any problems.
+    Ok,
+    /// Finished, but some requests or lookups failed along the way.
+    Partial { reasons: Vec<String> },
+    /// Could not produce results at all.
+    Failed { reason: String },
+}
+
+/// Structured result of running one source.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceOutcome {
+    pub source: &'static str,
+    #[serde(flatten)]
+    pub status: SourceStatus,
+    pub hosts_found: usize,
+
+    /// Request attempts made by the source, retries included
+    pub attempts: usize,
+    pub retries: usize,
+
+    /// API quota left after the run, for sources whose API reports one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<Quota>,
+
+    /// Requests sent to a third-party API, counted against its quota
+    #[serde(skip_serializing_if = "is_zero")]
+    pub api_requests: usize,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl SourceOutcome {
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, SourceStatus::Failed { .. })
+    }
+}
+
+/// Run a source, turning errors and panics into a `SourceOutcome` instead of
+/// propagating them, so one broken source cannot take down the whole run.
+pub fn run_isolated(
+    src: &dyn SubdomainSource,
+    cfg: &DiscoveryConfig,
+    ctx: &SourceContext,
+) -> (SubdomainMap, SourceOutcome) {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| src.discover(cfg, ctx)));
+    let warnings = ctx.take_warnings();
+
+    let (map, status) = match result {
+        Ok(Ok(map)) if warnings.is_empty() => (map, SourceStatus::Ok),
+        Ok(Ok(map)) => (map, SourceStatus::Partial { reasons: warnings }),
+        Ok(Err(e)) => (
+            SubdomainMap::new(),
+            SourceStatus::Failed {
+                reason: format!("{:#}", e),
+            },
+        ),
+        Err(panic) => {
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            (
+                SubdomainMap::new(),
+                SourceStatus::Failed {
+                    reason: format!("panicked: {}", msg),
+                },
+            )
+        }
+    };
+
+    let outcome = SourceOutcome {
+        source: src.name(),
+        status,
+        hosts_found: map.hosts().len(),
+        attempts: ctx.attempts(),
+        retries: ctx.retries(),
+        quota: ctx.quota(),
+        api_requests: ctx.api_requests(),
+    };
+    (map, outcome)
+}
+
+/// Print a per-source summary for one target to stderr.
+pub fn print_summary(target: &str, outcomes: &[SourceOutcome]) {
+    eprintln!(
+        "{}",
+        format!("[*] Source summary for {}:", target)
+            .magenta()
+            .bold()
+    );
+
+    for outcome in outcomes {
+        let attempts = attempts_note(outcome);
+        match &outcome.status {
+            SourceStatus::Ok => {
+                eprintln!(
+                    "    {} {} ({} hosts){}",
+                    "ok".green().bold(),
+                    outcome.source,
+                    outcome.hosts_found,
+                    attempts
+                );
+            }
+            SourceStatus::Partial { reasons } => {
+                eprintln!(
+                    "    {} {} ({} hosts, {} error(s)){}",
+                    "partial".yellow().bold(),
+                    outcome.source,
+                    outcome.hosts_found,
+                    reasons.len(),
+                    attempts
+                );
+                for reason in reasons.iter().take(MAX_REASONS_SHOWN) {
+                    eprintln!("        - {}", reason);
+                }
+                if reasons.len() > MAX_REASONS_SHOWN {
+                    eprintln!(
+                        "        - ... and {} more",
+                        reasons.len() - MAX_REASONS_SHOWN
+                    );
+                }
+            }
+            SourceStatus::Failed { reason } => {
+                eprintln!(
+                    "    {} {}: {}{}",
+                    "failed".red().bold(),
+                    outcome.source,
+                    reason,
+                    attempts
+                );
+            }
+        }
+    }
+}
+
+/// " [5 attempt(s), 2 retries, 5 request(s), quota 48/50 left]" style
+/// suffix; empty for sources that made no requests. The request count is only
+/// there for third-party APIs, and the quota only when the API reports it.
+fn attempts_note(outcome: &SourceOutcome) -> String {
+    let mut parts = match (outcome.attempts, outcome.retries) {
+        (0, _) => return String::new(),
+        (attempts, 0) => vec![format!("{} attempt(s)", attempts)],
+        (attempts, retries) => vec![
+            format!("{} attempt(s)", attempts),
+            format!("{} retries", retries),
+        ],
+    };
+
+    if outcome.api_requests > 0 {
+        parts.push(format!("{} request(s)", outcome.api_requests));
+    }
+    match outcome.quota {
+        Some(Quota {
+            remaining,
+            limit: Some(limit),
+        }) => parts.push(format!("quota {}/{} left", remaining, limit)),
+        Some(Quota {
+            remaining,
+            limit: None,
+        }) => parts.push(format!("quota {} left", remaining)),
+        None => {}
+    }
+
+    format!(" [{}]", parts.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    struct Flaky;
+
+    impl SubdomainSource for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn discover(&self, cfg: &DiscoveryConfig, ctx: &SourceContext) -> Result<SubdomainMap> {
+            if cfg.workers == 0 {
+                anyhow::bail!("upstream returned 502");
+            }
+            ctx.warn("one page timed out");
+            let mut map = SubdomainMap::new();
+            map.add_url(&cfg.start_url, &cfg.root_domain, self.name());
+            Ok(map)
+        }
+    }
+
+    fn cfg(workers: usize) -> DiscoveryConfig {
+        DiscoveryConfig {
+            workers,
+            ..DiscoveryConfig::for_test("https://www.example.com")
+        }
+    }
+
+    #[test]
+    fn test_failures_and_warnings_are_captured() {
+        let (map, outcome) = run_isolated(&Flaky, &cfg(0), &SourceContext::new());
+        assert!(map.hosts().is_empty());
+        assert!(outcome.is_failed());
+
+        let (map, outcome) = run_isolated(&Flaky, &cfg(1), &SourceContext::new());
+        assert_eq!(map.hosts(), vec!["www.example.com"]);
+        assert!(
+            matches!(outcome.status, SourceStatus::Partial { ref reasons } if reasons.len() == 1)
+        );
+    }
+
+    #[test]
+    fn test_attempts_note_counts_requests_next_to_quota() {
+        let mut outcome = SourceOutcome {
+            source: "certspotter",
+            status: SourceStatus::Ok,
+            hosts_found: 0,
+            attempts: 3,
+            retries: 1,
+            quota: None,
+            api_requests: 3,
+        };
+        assert_eq!(
+            attempts_note(&outcome),
+            " [3 attempt(s), 1 retries, 3 request(s)]"
+        );
+
+        outcome.quota = Some(Quota {
+            remaining: 47,
+            limit: Some(50),
+        });
+        assert_eq!(
+            attempts_note(&outcome),
+            " [3 attempt(s), 1 retries, 3 request(s), quota 47/50 left]"
+        );
+    }
+}
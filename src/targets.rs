@@ -0,0 +1,100 @@
+// src/targets.rs
+
+use std::io::BufRead;
+
+use anyhow::{Context, Result};
+use url::Url;
+
+use crate::subdomains::extract_root_domain;
+
+/// One thing to scan: where to start crawling and which domain to stay within.
+#[derive(Clone, Debug)]
+pub struct Target {
+    /// The target exactly as the user gave it (URL or bare domain)
+    pub input: String,
+    pub start_url: Url,
+    pub root_domain: String,
+}
+
+impl Target {
+    /// Parse a target given as a URL ("https://www.example.com/app") or a bare
+    /// domain ("www.example.com", crawled over HTTPS).
+    ///
+    /// The root domain is derived from the host unless `root_domain` is given.
+    pub fn parse(input: &str, root_domain: Option<&str>) -> Result<Self> {
+        let input = input.trim();
+
+        let start_url = if input.contains("://") {
+            Url::parse(input).with_context(|| format!("invalid start URL: {}", input))?
+        } else {
+            Url::parse(&format!("https://{}/", input.trim_end_matches('.')))
+                .with_context(|| format!("invalid target domain: {}", input))?
+        };
+
+        let host = start_url
+            .host_str()
+            .with_context(|| format!("Cannot derive root domain from URL {} without host", input))?
+            .to_lowercase();
+
+        let root_domain = if let Some(rd) = root_domain {
+            rd.trim().trim_end_matches('.').to_lowercase()
+        } else if let Some(root) = extract_root_domain(&host) {
+            root
+        } else {
+            return Err(anyhow::anyhow!(
+                "Cannot derive root domain from host: {}. Please specify --root-domain",
+                host
+            ));
+        };
+
+        Ok(Self {
+            input: input.to_string(),
+            start_url,
+            root_domain,
+        })
+    }
+}
+
+/// Read target lines, skipping blanks and `#` comments.
+pub fn read_target_lines(reader: impl BufRead) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+
+    for line in reader.lines() {
+        let line = line.context("failed to read targets")?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        out.push(line.to_string());
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url_and_bare_domain() {
+        let t = Target::parse("https://www.example.co.uk/login", None).unwrap();
+        assert_eq!(t.root_domain, "example.co.uk");
+        assert_eq!(t.start_url.as_str(), "https://www.example.co.uk/login");
+
+        let t = Target::parse("api.Example.com", None).unwrap();
+        assert_eq!(t.root_domain, "example.com");
+        assert_eq!(t.start_url.as_str(), "https://api.example.com/");
+
+        let t = Target::parse("a.b.example.com", Some("b.example.com")).unwrap();
+        assert_eq!(t.root_domain, "b.example.com");
+    }
+
+    #[test]
+    fn test_read_target_lines() {
+        let input = "example.com\n\n# staging\n  https://dev.example.org/  \n";
+        assert_eq!(
+            read_target_lines(input.as_bytes()).unwrap(),
+            vec!["example.com", "https://dev.example.org/"]
+        );
+    }
+}
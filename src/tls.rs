@@ -0,0 +1,200 @@
+// src/tls.rs
+
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use openssl::nid::Nid;
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::x509::X509;
+
+use crate::subdomains::host_in_scope;
+
+/// Names a certificate was issued for.
+#[derive(Debug, Default, PartialEq)]
+pub struct CertificateNames {
+    /// Subject common name, if it has one
+    pub common_name: Option<String>,
+
+    /// DNS entries of the Subject Alternative Name extension
+    pub alt_names: Vec<String>,
+}
+
+impl CertificateNames {
+    /// In-scope names, lowercased, each with the field it came from ("cn" or
+    /// "san"). Wildcard entries are skipped, as crt.sh results are.
+    pub fn in_scope(&self, root_domain: &str) -> Vec<(String, &'static str)> {
+        let cn = self.common_name.iter().map(|n| (n, "cn"));
+        let sans = self.alt_names.iter().map(|n| (n, "san"));
+
+        cn.chain(sans)
+            .map(|(name, field)| (name.trim().trim_end_matches('.').to_lowercase(), field))
+            .filter(|(name, _)| !name.contains('*') && host_in_scope(name, root_domain))
+            .collect()
+    }
+}
+
+/// Connect to `port` on one of `addrs` (what `host` resolved to), complete a
+/// TLS handshake (with SNI for `host`, without verifying the chain, so
+/// self-signed and expired certificates work too) and read the names off the
+/// leaf certificate.
+pub fn fetch_certificate_names(
+    host: &str,
+    addrs: &[IpAddr],
+    port: u16,
+    timeout: Duration,
+) -> Result<CertificateNames> {
+    let stream = connect(host, addrs, port, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut builder = SslConnector::builder(SslMethod::tls_client())?;
+    builder.set_verify(SslVerifyMode::NONE);
+    let connector = builder.build();
+
+    let mut config = connector.configure()?;
+    config.set_verify_hostname(false);
+    let tls = config
+        .connect(host, stream)
+        .map_err(|e| anyhow::anyhow!("TLS handshake with {}:{} failed: {}", host, port, e))?;
+
+    let cert = tls
+        .ssl()
+        .peer_certificate()
+        .with_context(|| format!("{}:{} sent no certificate", host, port))?;
+    Ok(certificate_names(&cert))
+}
+
+/// Try every address of `host` until one accepts the connection.
+fn connect(host: &str, addrs: &[IpAddr], port: u16, timeout: Duration) -> Result<TcpStream> {
+    let mut last_err = None;
+    for &ip in addrs {
+        match TcpStream::connect_timeout(&SocketAddr::new(ip, port), timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+
+    match last_err {
+        Some(e) => Err(e).with_context(|| format!("failed to connect to {}:{}", host, port)),
+        None => anyhow::bail!("{} has no addresses", host),
+    }
+}
+
+/// Common name and DNS Subject Alternative Names of a certificate.
+pub fn certificate_names(cert: &X509) -> CertificateNames {
+    let common_name = cert
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()
+        .and_then(|entry| entry.data().as_utf8().ok())
+        .map(|cn| cn.to_string());
+
+    let alt_names = cert
+        .subject_alt_names()
+        .map(|names| {
+            names
+                .iter()
+                .filter_map(|name| name.dnsname().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    CertificateNames {
+        common_name,
+        alt_names,
+    }
+}
+
+/// A self-signed certificate and a TLS server presenting it on localhost.
+#[cfg(test)]
+pub(crate) mod test_server {
+    use std::net::TcpListener;
+    use std::thread;
+
+    use openssl::asn1::Asn1Time;
+    use openssl::bn::{BigNum, MsbOption};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::pkey::{PKey, Private};
+    use openssl::ssl::{SslAcceptor, SslMethod};
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::{X509, X509NameBuilder};
+
+    /// A fresh key and a self-signed certificate for `common_name` and `alt_names`.
+    pub fn self_signed(common_name: &str, alt_names: &[&str]) -> (PKey<Private>, X509) {
+        let group = EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", common_name).unwrap();
+        let name = name.build();
+
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_serial_number(&serial.to_asn1_integer().unwrap())
+            .unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(&name).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let mut san = SubjectAlternativeName::new();
+        for alt in alt_names {
+            san.dns(alt);
+        }
+        let san = san.build(&cert.x509v3_context(None, None)).unwrap();
+        cert.append_extension(san).unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+        (key, cert.build())
+    }
+
+    /// Serve a certificate for `common_name` and `alt_names` on 127.0.0.1,
+    /// returning the port. The server handles any number of handshakes.
+    pub fn spawn(common_name: &str, alt_names: &[&str]) -> u16 {
+        let (key, cert) = self_signed(common_name, alt_names);
+
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // The client hangs up right after the handshake
+                let _ = acceptor.accept(stream);
+            }
+        });
+
+        port
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fetch_certificate_names_from_self_signed_cert() {
+        let port = test_server::spawn(
+            "portal.example.com",
+            &["www.example.com", "*.dev.example.com", "other.org"],
+        );
+
+        let localhost = [IpAddr::from([127, 0, 0, 1])];
+        let names =
+            fetch_certificate_names("localhost", &localhost, port, Duration::from_secs(5)).unwrap();
+        assert_eq!(names.common_name.as_deref(), Some("portal.example.com"));
+        assert_eq!(
+            names.alt_names,
+            vec!["www.example.com", "*.dev.example.com", "other.org"]
+        );
+    }
+}
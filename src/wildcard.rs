@@ -0,0 +1,196 @@
+// src/wildcard.rs
+
+use std::collections::{BTreeSet, HashMap};
+use std::net::IpAddr;
+
+use rand::Rng;
+use rand::distributions::Alphanumeric;
+use serde::Serialize;
+
+use crate::dns::Resolver;
+use crate::subdomains::SubdomainMap;
+
+/// The source whose DNS answers are checked (the only one recording answers).
+const RESOLVING_SOURCE: &str = "dns-bruteforce";
+
+/// How many random labels to probe per parent domain.
+/// Several probes catch wildcards that rotate between a pool of addresses.
+const PROBES_PER_PARENT: usize = 3;
+
+/// A host whose DNS answers were indistinguishable from a wildcard record.
+#[derive(Debug, Clone, Serialize)]
+pub struct WildcardMatch {
+    pub host: String,
+    /// The domain whose wildcard (`*.<parent>`) produced the same answers.
+    pub parent: String,
+    pub addrs: BTreeSet<IpAddr>,
+}
+
+/// Probes random labels to learn which domains answer for any name below them.
+pub struct WildcardDetector<'a> {
+    resolver: &'a Resolver,
+    // parent -> wildcard answer set (None if the parent has no wildcard)
+    cache: HashMap<String, Option<BTreeSet<IpAddr>>>,
+}
+
+impl<'a> WildcardDetector<'a> {
+    pub fn new(resolver: &'a Resolver) -> Self {
+        Self {
+            resolver,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Answers returned for random names under `parent`, if it has a wildcard.
+    pub fn wildcard_answers(&mut self, parent: &str) -> Option<&BTreeSet<IpAddr>> {
+        if !self.cache.contains_key(parent) {
+            let answers = self.probe(parent);
+            if let Some(addrs) = &answers {
+                eprintln!(
+                    "[!] Wildcard DNS detected for *.{} ({} address(es))",
+                    parent,
+                    addrs.len()
+                );
+            }
+            self.cache.insert(parent.to_string(), answers);
+        }
+
+        self.cache.get(parent).and_then(|a| a.as_ref())
+    }
+
+    fn probe(&self, parent: &str) -> Option<BTreeSet<IpAddr>> {
+        let mut rng = rand::thread_rng();
+        let mut seen = BTreeSet::new();
+        let mut any_resolved = false;
+
+        for _ in 0..PROBES_PER_PARENT {
+            let label: String = (&mut rng)
+                .sample_iter(&Alphanumeric)
+                .take(16)
+                .map(|c| char::from(c).to_ascii_lowercase())
+                .collect();
+
+            // Resolver errors are treated like NXDOMAIN; we would rather keep
+            // a result than suppress it on a flaky probe.
+            if let Ok(Some(lookup)) = self.resolver.resolve(&format!("{}.{}", label, parent)) {
+                any_resolved = true;
+                seen.extend(lookup.addrs);
+            }
+        }
+
+        any_resolved.then_some(seen)
+    }
+}
+
+/// Retract DNS answers that only ever came from a wildcard record.
+///
+/// The root domain and the immediate parent of each resolved host are probed.
+/// A host only the resolver reported is removed; one that other sources also
+/// reported keeps their findings and loses just its answers and the resolver
+/// attribution. Either way it is returned so it can be reported instead of
+/// silently lost.
+pub fn filter_wildcards(
+    map: &mut SubdomainMap,
+    root_domain: &str,
+    resolver: &Resolver,
+) -> Vec<WildcardMatch> {
+    let mut detector = WildcardDetector::new(resolver);
+    let mut matches = Vec::new();
+
+    let resolved: Vec<(String, BTreeSet<IpAddr>)> = map
+        .resolved_hosts()
+        .into_iter()
+        .map(|(host, addrs)| (host.to_string(), addrs.clone()))
+        .collect();
+
+    for (host, addrs) in resolved {
+        if host == root_domain || addrs.is_empty() {
+            continue;
+        }
+
+        let Some((_, parent)) = host.split_once('.') else {
+            continue;
+        };
+
+        let mut parents = vec![parent.to_string()];
+        if parent != root_domain {
+            parents.push(root_domain.to_string());
+        }
+
+        for parent in parents {
+            let Some(wildcard) = detector.wildcard_answers(&parent) else {
+                continue;
+            };
+
+            if addrs.is_subset(wildcard) {
+                map.retract_answers(&host, RESOLVING_SOURCE);
+                matches.push(WildcardMatch {
+                    host: host.clone(),
+                    parent,
+                    addrs: addrs.clone(),
+                });
+                break;
+            }
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use url::Url;
+
+    use super::*;
+    use crate::dns::{self, stub_server};
+
+    #[test]
+    fn test_wildcard_answers_are_suppressed() {
+        let server = stub_server::spawn(HashMap::from([
+            ("*.example.com".to_string(), Ipv4Addr::new(10, 0, 0, 99)),
+            ("www.example.com".to_string(), Ipv4Addr::new(10, 0, 0, 1)),
+        ]));
+        let resolver = Resolver::new(vec![server], dns::DEFAULT_TIMEOUT);
+
+        let mut map = SubdomainMap::new();
+        for (host, ip) in [("www.example.com", 1), ("bogus.example.com", 99)] {
+            let url = Url::parse(&format!("https://{}", host)).unwrap();
+            map.add_url(&url, "example.com", "dns-bruteforce");
+            map.record_answers(host, &[IpAddr::from([10, 0, 0, ip])]);
+        }
+
+        let matches = filter_wildcards(&mut map, "example.com", &resolver);
+
+        assert_eq!(map.hosts(), vec!["www.example.com"]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].host, "bogus.example.com");
+        assert_eq!(matches[0].parent, "example.com");
+    }
+
+    #[test]
+    fn test_wildcard_match_keeps_other_sources() {
+        let server = stub_server::spawn(HashMap::from([(
+            "*.example.com".to_string(),
+            Ipv4Addr::new(10, 0, 0, 99),
+        )]));
+        let resolver = Resolver::new(vec![server], dns::DEFAULT_TIMEOUT);
+
+        let mut map = SubdomainMap::new();
+        let url = Url::parse("https://shop.example.com/cart").unwrap();
+        map.add_url(&url, "example.com", "crtsh");
+        map.add_url(&url, "example.com", "dns-bruteforce");
+        map.record_answers("shop.example.com", &[IpAddr::from([10, 0, 0, 99])]);
+
+        let matches = filter_wildcards(&mut map, "example.com", &resolver);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].host, "shop.example.com");
+        let entry = map.get("shop.example.com").unwrap();
+        assert!(entry.answers.is_empty());
+        assert_eq!(entry.sources.iter().collect::<Vec<_>>(), vec!["crtsh"]);
+        assert!(entry.paths.contains("/cart"));
+    }
+}